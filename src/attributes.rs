@@ -0,0 +1,471 @@
+#![allow(non_snake_case)]
+
+// IEC 61850-7-3 common attribute types, most carried as bit strings. IECData::bit_string keeps the
+// octets as the decoder leaves them: reversed and bit reversed, so bit n of the attribute is
+// bit n of val read as a big endian integer
+
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+use crate::error::GooseError;
+use crate::types::{*};
+
+// value of a bit string with exactly bits significant bits, bit n at 1<<n
+pub fn bitStringValue(data: &IECData, bits: usize) -> Result<u32,GooseError>{
+    match data{
+        IECData::bit_string{ padding, val } if val.len() * 8 == bits + *padding as usize && val.len() <= 4 => {
+            Ok(val.iter().fold(0_u32, |value, byte| (value << 8) | *byte as u32) & ((1_u64 << bits) - 1) as u32)
+        },
+        IECData::bit_string{ padding, val } => Err(GooseError{ message: format!("expected bit_string({}), got bit_string({})", bits, (val.len() * 8).saturating_sub(*padding as usize)), pos: 0 }),
+        other => Err(GooseError{ message: format!("expected bit_string({}), got {:?}", bits, other), pos: 0 }),
+    }
+}
+
+pub fn bitStringData(value: u32, bits: usize) -> IECData{
+    let octets = bits.div_ceil(8);
+    IECData::bit_string{ padding: (octets * 8 - bits) as u8, val: value.to_be_bytes()[4 - octets..].to_vec() }
+}
+
+// Dbpos and Tcmd read bits 0 and 1 as a 2 bit number with bit 0 the most significant,
+// swapping the two bits converts both ways
+fn twoBitPosition(value: u32) -> u32{
+    ((value & 0x01) << 1) | ((value >> 1) & 0x01)
+}
+
+// double point status
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Dbpos {
+    #[default]
+    Intermediate,
+    Off,
+    On,
+    Bad,
+}
+
+impl Dbpos {
+    pub const BITS:usize = 2;
+
+    pub fn from_bits(value: u32) -> Dbpos{
+        match twoBitPosition(value){
+            0 => Dbpos::Intermediate,
+            1 => Dbpos::Off,
+            2 => Dbpos::On,
+            _ => Dbpos::Bad,
+        }
+    }
+
+    pub fn to_bits(&self) -> u32{
+        let position = match self{
+            Dbpos::Intermediate => 0,
+            Dbpos::Off => 1,
+            Dbpos::On => 2,
+            Dbpos::Bad => 3,
+        };
+        twoBitPosition(position)
+    }
+}
+
+impl TryFrom<&IECData> for Dbpos {
+    type Error = GooseError;
+    fn try_from(data: &IECData) -> Result<Dbpos,GooseError>{
+        Ok(Dbpos::from_bits(bitStringValue(data, Dbpos::BITS)?))
+    }
+}
+
+impl From<Dbpos> for IECData {
+    fn from(value: Dbpos) -> IECData{
+        bitStringData(value.to_bits(), Dbpos::BITS)
+    }
+}
+
+impl fmt::Display for Dbpos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self{
+            Dbpos::Intermediate => "intermediate-state",
+            Dbpos::Off => "off",
+            Dbpos::On => "on",
+            Dbpos::Bad => "bad-state",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// step position command of tap changers
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Tcmd {
+    #[default]
+    Stop,
+    Lower,
+    Higher,
+    Reserved,
+}
+
+impl Tcmd {
+    pub const BITS:usize = 2;
+
+    pub fn from_bits(value: u32) -> Tcmd{
+        match twoBitPosition(value){
+            0 => Tcmd::Stop,
+            1 => Tcmd::Lower,
+            2 => Tcmd::Higher,
+            _ => Tcmd::Reserved,
+        }
+    }
+
+    pub fn to_bits(&self) -> u32{
+        let position = match self{
+            Tcmd::Stop => 0,
+            Tcmd::Lower => 1,
+            Tcmd::Higher => 2,
+            Tcmd::Reserved => 3,
+        };
+        twoBitPosition(position)
+    }
+}
+
+impl TryFrom<&IECData> for Tcmd {
+    type Error = GooseError;
+    fn try_from(data: &IECData) -> Result<Tcmd,GooseError>{
+        Ok(Tcmd::from_bits(bitStringValue(data, Tcmd::BITS)?))
+    }
+}
+
+impl From<Tcmd> for IECData {
+    fn from(value: Tcmd) -> IECData{
+        bitStringData(value.to_bits(), Tcmd::BITS)
+    }
+}
+
+impl fmt::Display for Tcmd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self{
+            Tcmd::Stop => "stop",
+            Tcmd::Lower => "lower",
+            Tcmd::Higher => "higher",
+            Tcmd::Reserved => "reserved",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// direction of a protection start or trip, dirGeneral and dirPhsA.. of ACD. unlike the types
+// above it is an ENUMERATED and travels as an integer, the SCL importer maps it to int8
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Dir {
+    #[default]
+    Unknown,
+    Forward,
+    Backward,
+    Both,
+}
+
+impl TryFrom<&IECData> for Dir {
+    type Error = GooseError;
+    fn try_from(data: &IECData) -> Result<Dir,GooseError>{
+        let value = match data{
+            IECData::int8(val) => *val as i64,
+            IECData::int16(val) => *val as i64,
+            IECData::int32(val) => *val as i64,
+            IECData::int64(val) => *val,
+            IECData::int8u(val) => *val as i64,
+            IECData::int16u(val) => *val as i64,
+            IECData::int32u(val) => *val as i64,
+            other => return Err(GooseError{ message: format!("expected an integer direction, got {:?}", other), pos: 0 }),
+        };
+        match value{
+            0 => Ok(Dir::Unknown),
+            1 => Ok(Dir::Forward),
+            2 => Ok(Dir::Backward),
+            3 => Ok(Dir::Both),
+            _ => Err(GooseError{ message: format!("invalid direction {}", value), pos: 0 }),
+        }
+    }
+}
+
+impl From<Dir> for IECData {
+    fn from(value: Dir) -> IECData{
+        IECData::int8(value as i8)
+    }
+}
+
+impl fmt::Display for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self{
+            Dir::Unknown => "unknown",
+            Dir::Forward => "forward",
+            Dir::Backward => "backward",
+            Dir::Both => "both",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// quality validity, bits 0 and 1 like Dbpos
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Validity {
+    #[default]
+    Good,
+    Invalid,
+    Reserved,
+    Questionable,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum QualitySource {
+    #[default]
+    Process,
+    Substituted,
+}
+
+// 13 bit quality
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub struct Quality {
+    pub validity: Validity,
+    // detailQual
+    pub overflow: bool,
+    pub outOfRange: bool,
+    pub badReference: bool,
+    pub oscillatory: bool,
+    pub failure: bool,
+    pub oldData: bool,
+    pub inconsistent: bool,
+    pub inaccurate: bool,
+    pub source: QualitySource,
+    pub test: bool,
+    pub operatorBlocked: bool,
+}
+
+impl Quality {
+    pub const BITS:usize = 13;
+
+    pub fn from_bits(value: u32) -> Quality{
+        let bit = |n: u32| value & (1 << n) != 0;
+        let validity = match (bit(0), bit(1)){
+            (false, false) => Validity::Good,
+            (false, true) => Validity::Invalid,
+            (true, false) => Validity::Reserved,
+            (true, true) => Validity::Questionable,
+        };
+        Quality{
+            validity,
+            overflow: bit(2),
+            outOfRange: bit(3),
+            badReference: bit(4),
+            oscillatory: bit(5),
+            failure: bit(6),
+            oldData: bit(7),
+            inconsistent: bit(8),
+            inaccurate: bit(9),
+            source: if bit(10) {QualitySource::Substituted} else {QualitySource::Process},
+            test: bit(11),
+            operatorBlocked: bit(12),
+        }
+    }
+
+    pub fn to_bits(&self) -> u32{
+        let validity = match self.validity{
+            Validity::Good => 0b00,
+            Validity::Invalid => 0b10,
+            Validity::Reserved => 0b01,
+            Validity::Questionable => 0b11,
+        };
+        let flags = [
+            self.overflow, self.outOfRange, self.badReference, self.oscillatory, self.failure, self.oldData,
+            self.inconsistent, self.inaccurate, self.source == QualitySource::Substituted, self.test, self.operatorBlocked,
+        ];
+        flags.iter().enumerate().fold(validity, |value, (n, flag)| value | ((*flag as u32) << (n + 2)))
+    }
+
+    pub fn is_good(&self) -> bool{
+        self.validity == Validity::Good
+    }
+}
+
+impl fmt::Display for Validity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let text = match self{
+            Validity::Good => "good",
+            Validity::Invalid => "invalid",
+            Validity::Reserved => "reserved",
+            Validity::Questionable => "questionable",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// validity followed by the flags set, questionable [oldData, test]
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let flags = [
+            (self.overflow, "overflow"),
+            (self.outOfRange, "outOfRange"),
+            (self.badReference, "badReference"),
+            (self.oscillatory, "oscillatory"),
+            (self.failure, "failure"),
+            (self.oldData, "oldData"),
+            (self.inconsistent, "inconsistent"),
+            (self.inaccurate, "inaccurate"),
+            (self.source == QualitySource::Substituted, "substituted"),
+            (self.test, "test"),
+            (self.operatorBlocked, "operatorBlocked"),
+        ];
+        write!(f, "{}", self.validity)?;
+        let set: Vec<&str> = flags.iter().filter(|(set, _)| *set).map(|(_, name)| *name).collect();
+        if !set.is_empty(){
+            write!(f, " [{}]", set.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&IECData> for Quality {
+    type Error = GooseError;
+    fn try_from(data: &IECData) -> Result<Quality,GooseError>{
+        Ok(Quality::from_bits(bitStringValue(data, Quality::BITS)?))
+    }
+}
+
+impl From<Quality> for IECData {
+    fn from(value: Quality) -> IECData{
+        bitStringData(value.to_bits(), Quality::BITS)
+    }
+}
+
+// IEC 61850-7-3 name of UtcTime, its Display gives the ISO 8601 form
+pub type Timestamp = UtcTime;
+
+impl From<Timestamp> for IECData {
+    fn from(value: Timestamp) -> IECData{
+        IECData::utc_time(value)
+    }
+}
+
+impl IECData {
+    pub fn as_bool(&self) -> Option<bool>{
+        match self{
+            IECData::boolean(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    pub fn as_dbpos(&self) -> Option<Dbpos>{
+        Dbpos::try_from(self).ok()
+    }
+
+    pub fn as_quality(&self) -> Option<Quality>{
+        Quality::try_from(self).ok()
+    }
+
+    pub fn as_tcmd(&self) -> Option<Tcmd>{
+        Tcmd::try_from(self).ok()
+    }
+
+    pub fn as_dir(&self) -> Option<Dir>{
+        Dir::try_from(self).ok()
+    }
+
+    pub fn as_timestamp(&self) -> Option<Timestamp>{
+        match self{
+            IECData::utc_time(val) => Some(*val),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu_encoder::encodeIECDataElement;
+    use crate::pdu_decoder::decodeIECDataElement;
+
+    fn encode(data: &IECData) -> Vec<u8>{
+        let mut buffer = vec![0_u8;16];
+        let size = encodeIECDataElement(data, &mut buffer, 0);
+        buffer.truncate(size);
+        buffer
+    }
+
+    fn decode(wire: &[u8]) -> IECData{
+        let (size, data) = decodeIECDataElement(wire, 0).unwrap();
+        assert_eq!(size, wire.len());
+        data
+    }
+
+    #[test]
+    fn dbpos_on_the_wire(){
+        // bit 0 first on the wire, on is 10
+        let wire = [0x84, 0x02, 0x06, 0x80];
+        assert_eq!(encode(&Dbpos::On.into()), wire);
+        assert_eq!(decode(&wire).as_dbpos(), Some(Dbpos::On));
+
+        let expected = [(Dbpos::Intermediate, 0x00), (Dbpos::Off, 0x40), (Dbpos::On, 0x80), (Dbpos::Bad, 0xc0)];
+        for (dbpos, octet) in expected{
+            let wire = [0x84, 0x02, 0x06, octet];
+            assert_eq!(encode(&dbpos.into()), wire);
+            assert_eq!(decode(&wire).as_dbpos(), Some(dbpos));
+        }
+        assert_eq!(Dbpos::On.to_string(), "on");
+        assert_eq!(Dbpos::Intermediate.to_string(), "intermediate-state");
+    }
+
+    #[test]
+    fn tcmd_on_the_wire(){
+        let expected = [(Tcmd::Stop, 0x00), (Tcmd::Lower, 0x40), (Tcmd::Higher, 0x80), (Tcmd::Reserved, 0xc0)];
+        for (tcmd, octet) in expected{
+            let wire = [0x84, 0x02, 0x06, octet];
+            assert_eq!(encode(&tcmd.into()), wire);
+            assert_eq!(decode(&wire).as_tcmd(), Some(tcmd));
+        }
+        assert_eq!(Tcmd::Higher.to_string(), "higher");
+    }
+
+    #[test]
+    fn quality_on_the_wire(){
+        // validity questionable 11 in bits 0 and 1, oldData bit 7, 13 bits in two octets
+        let quality = Quality{ validity: Validity::Questionable, oldData: true, ..Default::default() };
+        let wire = [0x84, 0x03, 0x03, 0xc1, 0x00];
+        assert_eq!(encode(&quality.into()), wire);
+        assert_eq!(decode(&wire).as_quality(), Some(quality));
+        assert_eq!(quality.to_string(), "questionable [oldData]");
+
+        // invalid is 01, bit 1 set
+        let quality = Quality{ validity: Validity::Invalid, source: QualitySource::Substituted, test: true, operatorBlocked: true, ..Default::default() };
+        let wire = [0x84, 0x03, 0x03, 0x40, 0x38];
+        assert_eq!(encode(&quality.into()), wire);
+        assert_eq!(decode(&wire).as_quality(), Some(quality));
+        assert_eq!(quality.to_string(), "invalid [substituted, test, operatorBlocked]");
+
+        let reserved = decode(&[0x84, 0x03, 0x03, 0x80, 0x00]).as_quality().unwrap();
+        assert_eq!(reserved.validity, Validity::Reserved);
+        assert_eq!(Quality::default().to_string(), "good");
+        assert!(Quality::default().is_good());
+    }
+
+    #[test]
+    fn every_quality_round_trips(){
+        for bits in 0..(1_u32 << Quality::BITS){
+            let quality = Quality::from_bits(bits);
+            assert_eq!(quality.to_bits(), bits);
+            assert_eq!(IECData::from(quality).as_quality(), Some(quality));
+        }
+    }
+
+    #[test]
+    fn wrong_width_or_type(){
+        // a quality is not a Dbpos and the other way round
+        assert!(decode(&[0x84, 0x03, 0x03, 0xc1, 0x00]).as_dbpos().is_none());
+        assert!(decode(&[0x84, 0x02, 0x06, 0x80]).as_quality().is_none());
+        let e = Dbpos::try_from(&IECData::boolean(true)).unwrap_err();
+        assert_eq!(e.message, "expected bit_string(2), got boolean(true)");
+    }
+
+    #[test]
+    fn dir(){
+        assert!(matches!(IECData::from(Dir::Backward), IECData::int8(2)));
+        assert_eq!(decode(&[0x85, 0x01, 0x01]).as_dir(), Some(Dir::Forward));
+        assert_eq!(IECData::int32u(3).as_dir(), Some(Dir::Both));
+        assert!(IECData::int8(4).as_dir().is_none());
+        assert_eq!(Dir::Unknown.to_string(), "unknown");
+    }
+}
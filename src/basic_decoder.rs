@@ -1,150 +1,212 @@
 use crate::error::GooseError;
+use crate::utc_time::UtcTime;
 
-pub fn decode_boolean(value:&mut bool,buffer: &[u8],pos:usize) ->usize{
+pub fn check_bounds(buffer: &[u8],pos:usize,length:usize) ->Result<(),GooseError>{
+    match pos.checked_add(length) {
+        Some(end) if end<=buffer.len() => Ok(()),
+        _ => Err(GooseError{ message: "buffer too short".to_owned(), pos }),
+    }
+}
+
+pub fn decode_boolean(value:&mut bool,buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    if length!=1 {
+        return Err(GooseError{ message: format!("unexpected boolean length {}",length), pos });
+    }
+    check_bounds(buffer,pos,length)?;
     *value=buffer[pos]!=0;
-    pos+1
+    Ok(pos+1)
 }
 
-pub fn decode_string(value:& mut String,buffer: &[u8],pos:usize,length:usize) ->usize{
+pub fn decode_string(value:& mut String,buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    check_bounds(buffer,pos,length)?;
     *value=String::from_utf8_lossy(&buffer[pos..pos+length]).to_string();
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_octet_string(value:& mut [u8],buffer: &[u8],pos:usize,length:usize) ->usize{
-    value[0..length].copy_from_slice(&buffer[pos..pos+length]);
-    pos+length
+pub fn decode_octet_string(value:& mut [u8],buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    if length!=value.len() {
+        return Err(GooseError{ message: format!("unexpected octet string length {}, expected {}",length,value.len()), pos });
+    }
+    check_bounds(buffer,pos,length)?;
+    value.copy_from_slice(&buffer[pos..pos+length]);
+    Ok(pos+length)
 }
 
-pub fn decompress_integer(value: & mut [u8],buffer: &[u8],pos:usize,length:usize) {
-    let mut fill=0x00;
-    if buffer[pos] &0x80 == 0x80 {
-        fill=0xff;
+pub fn decode_utc_time(value:& mut UtcTime,buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    let mut bytes=[0_u8;8];
+    let new_pos=decode_octet_string(&mut bytes,buffer,pos,length)?;
+    *value=UtcTime::from_bytes(&bytes);
+    Ok(new_pos)
+}
+
+pub fn decompress_integer(value: & mut [u8],buffer: &[u8],pos:usize,length:usize) ->Result<(),GooseError>{
+    if length==0 {
+        return Err(GooseError{ message: "zero length integer".to_owned(), pos });
     }
-    let fill_length=value.len()-length; //fix me may panic
-    for i in 0..value.len()-fill_length{
-        value[i]=fill;
+    if length>value.len() {
+        return Err(GooseError{ message: format!("integer length {} exceeds {} bytes",length,value.len()), pos });
     }
-    value[fill_length..].copy_from_slice(&buffer[pos..pos+length]);
+    check_bounds(buffer,pos,length)?;
 
+    let fill= if buffer[pos] &0x80 == 0x80 {0xff} else {0x00};
+    let fill_length=value.len()-length;
+    value[..fill_length].fill(fill);
+    value[fill_length..].copy_from_slice(&buffer[pos..pos+length]);
+    Ok(())
 }
-pub fn decode_interger_8(value:&mut i8, buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;1];
-    bytes[0..].copy_from_slice(&buffer[pos..pos+length]);
+
+pub fn decode_interger_8(value:&mut i8, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    let mut bytes=[0_u8;1];
+    decompress_integer(& mut bytes,buffer,pos,length)?;
     *value=i8::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_interger_16(value:&mut i16, buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;2];
-    bytes[0..].copy_from_slice(&buffer[pos..pos+length]);
+pub fn decode_interger_16(value:&mut i16, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    let mut bytes=[0_u8;2];
+    decompress_integer(& mut bytes,buffer,pos,length)?;
     *value=i16::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_interger(value:&mut i32, buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;4];
-
-    decompress_integer(& mut bytes,buffer,pos,length);
+pub fn decode_interger(value:&mut i32, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    let mut bytes=[0_u8;4];
+    decompress_integer(& mut bytes,buffer,pos,length)?;
     *value=i32::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_interger_64(value:&mut i64, buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;8];
-
-    decompress_integer(& mut bytes,buffer,pos,length);
+pub fn decode_interger_64(value:&mut i64, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    let mut bytes=[0_u8;8];
+    decompress_integer(& mut bytes,buffer,pos,length)?;
     *value=i64::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_unsigned_8(value:&mut u8, buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;1];
-    bytes[0..].copy_from_slice(&buffer[pos..pos+length]);
+pub fn decode_unsigned_8(value:&mut u8, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    let mut bytes=[0_u8;1];
+    decompress_integer(& mut bytes,buffer,pos,length)?;
     *value=u8::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_unsigned_16(value:&mut u16, buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;2];
-    bytes[0..].copy_from_slice(&buffer[pos..pos+length]);
+pub fn decode_unsigned_16(value:&mut u16, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    let mut bytes=[0_u8;2];
+    decompress_integer(& mut bytes,buffer,pos,length)?;
     *value=u16::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
-pub fn decode_unsigned(value:&mut u32, buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;4];
-    decompress_integer(& mut bytes,buffer,pos,length);
+
+pub fn decode_unsigned(value:&mut u32, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    // a leading zero octet keeps the top bit of a 32bit value from reading as a sign
+    if length==5 {
+        check_bounds(buffer,pos,length)?;
+        if buffer[pos]!=0x00 {
+            return Err(GooseError{ message: "oversize unsigned interger".to_owned(), pos });
+        }
+        let mut bytes=[0_u8;4];
+        bytes.copy_from_slice(&buffer[pos+1..pos+5]);
+        *value=u32::from_be_bytes(bytes);
+        return Ok(pos+length);
+    }
+    let mut bytes=[0_u8;4];
+    decompress_integer(& mut bytes,buffer,pos,length)?;
     *value=u32::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_float(value:&mut f32,buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;4];
+pub fn decode_unsigned_64(value:&mut u64, buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    check_bounds(buffer,pos,length)?;
+    // a leading zero octet keeps the top bit of a 64bit value from reading as a sign
+    let (start,octets)= if length==9 {
+        if buffer[pos]!=0x00 {
+            return Err(GooseError{ message: "oversize unsigned interger".to_owned(), pos });
+        }
+        (pos+1,8)
+    } else {
+        (pos,length)
+    };
+    if octets==0 || octets>8 {
+        return Err(GooseError{ message: format!("unexpected unsigned length {}",length), pos });
+    }
+    let mut bytes=[0_u8;8];
+    bytes[8-octets..].copy_from_slice(&buffer[start..start+octets]);
+    *value=u64::from_be_bytes(bytes);
+    Ok(pos+length)
+}
 
+pub fn decode_float(value:&mut f32,buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    if length!=5 {
+        return Err(GooseError{ message: format!("unexpected float32 length {}",length), pos });
+    }
+    check_bounds(buffer,pos,length)?;
+    let mut bytes=[0_u8;4];
     bytes.copy_from_slice(&buffer[pos+1..pos+5]);
     *value=f32::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_float_64(value:&mut f64,buffer: &[u8],pos:usize,length:usize) ->usize{
-    let mut bytes=[0 as u8;8];
-
+pub fn decode_float_64(value:&mut f64,buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    if length!=9 {
+        return Err(GooseError{ message: format!("unexpected float64 length {}",length), pos });
+    }
+    check_bounds(buffer,pos,length)?;
+    let mut bytes=[0_u8;8];
     bytes.copy_from_slice(&buffer[pos+1..pos+9]);
     *value=f64::from_be_bytes(bytes);
-    pos+length
+    Ok(pos+length)
 }
 
-pub fn decode_bit_string(value:& mut [u8],padding:&mut u8,buffer: &[u8],pos:usize,_length:usize) ->usize{
+pub fn decode_bit_string(value:& mut [u8],padding:&mut u8,buffer: &[u8],pos:usize,length:usize) ->Result<usize,GooseError>{
+    if length==0 || length!=value.len()+1 {
+        return Err(GooseError{ message: format!("unexpected bit string length {}",length), pos });
+    }
+    check_bounds(buffer,pos,length)?;
+
     let mut new_pos=pos;
 
     *padding=buffer[new_pos];
+    if *padding>7 || (value.is_empty() && *padding!=0) {
+        return Err(GooseError{ message: format!("invalid bit string padding {}",*padding), pos });
+    }
     new_pos+=1;
 
-    for i in 0..value.len()  {
-        value[value.len()-i-1]=buffer[new_pos+i].reverse_bits();
+    let len=value.len();
+    for (i, byte) in buffer[new_pos..new_pos+len].iter().enumerate() {
+        value[len-i-1]=byte.reverse_bits();
     }
-    new_pos+value.len()
+    Ok(new_pos+len)
 }
 
 pub fn decode_tag_length(tag:&mut u8,value:&mut usize,buffer: &[u8],pos:usize) -> Result<usize, GooseError>{
 
+    check_bounds(buffer,pos,2)?;
+
     let mut new_pos=pos;
     *tag=buffer[new_pos];
     new_pos+=1;
 
     match buffer[new_pos] {
-        0x81=>{
-            new_pos+=1;
-            *value=buffer[new_pos] as usize;
+        0x81..=0x83=>{
+            let octets=(buffer[new_pos]&0x7f) as usize;
             new_pos+=1;
-
+            check_bounds(buffer,new_pos,octets)?;
+            *value=0;
+            for byte in &buffer[new_pos..new_pos+octets] {
+                *value=*value*0x100+*byte as usize;
+            }
+            new_pos+=octets;
         },
-        0x82=>{
-            new_pos+=1;
-            *value= buffer[new_pos] as usize *0x100;
-            new_pos+=1;
-            *value+= (buffer[new_pos])as usize;
-            new_pos+=1;
-
-        },
-        0x83=>{
-            new_pos+=1;
-            *value= buffer[new_pos] as usize *0x10000;
-            new_pos+=1;
-            *value+= buffer[new_pos] as usize *0x100;
-            new_pos+=1;
-            *value+= (buffer[new_pos])as usize;
+        0x00..=0x7f=>{
+            *value=buffer[new_pos] as usize;
             new_pos+=1;
-
         },
         _=>{
-            if buffer[new_pos]>0x83 {return Err(GooseError { message: "unexpexted legnth".to_owned(), pos: new_pos });}
-            *value=buffer[new_pos] as usize;
-            new_pos+=1;
-
+            return Err(GooseError { message: "unexpexted legnth".to_owned(), pos: new_pos });
         }
     }
-    if pos + *value > buffer.len(){
-        return Err(GooseError{ message: "tag len lagerger than buffer".to_owned(), pos: pos});
+    if check_bounds(buffer,new_pos,*value).is_err(){
+        return Err(GooseError{ message: "tag len lagerger than buffer".to_owned(), pos});
     }
     Ok(new_pos)
-}
\ No newline at end of file
+}
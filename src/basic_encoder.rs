@@ -1,68 +1,51 @@
+use crate::utc_time::UtcTime;
+
 pub fn encode_boolean(tag:u8,value:bool,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
     if !fill
     {
         return 1;
     }
 
-    let mut new_pos=pos;
-    buffer[new_pos]=tag;
-    new_pos+=1;
-    buffer[new_pos]=1;
-    new_pos+=1;
+    let mut new_pos=encode_tag_length(tag,1,buffer,pos,fill);
     buffer[new_pos]= if value{0xff} else{0x00};
     new_pos+=1;
     new_pos
 }
 
 pub fn encode_string(tag:u8,value:& String,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
-    let bytes=value.as_bytes();
-    
-    if !fill{
-        return bytes.len();
-    }
-
-    let mut new_pos=pos;
-    buffer[new_pos]=tag;
-    new_pos+=1;
-    buffer[new_pos]=bytes.len() as u8; //?
-    new_pos+=1;
-    for i in 0..bytes.len(){
-        buffer[new_pos]=bytes[i];
-        new_pos+=1;
-    }
-    new_pos
+    encode_octet_string(tag,value.as_bytes(),buffer,pos,fill)
 }
 
 pub fn encode_octet_string(tag:u8,value:& [u8],buffer: &mut[u8],pos:usize,fill:bool) ->usize{
-    
+
     if !fill{
         return value.len();
     }
 
-    let mut new_pos=pos;
-    buffer[new_pos]=tag;
-    new_pos+=1;
-    buffer[new_pos]=value.len() as u8; //?
-    new_pos+=1;
+    let new_pos=encode_tag_length(tag,value.len(),buffer,pos,fill);
 
     buffer[new_pos..new_pos+value.len()].copy_from_slice(value);
     new_pos+value.len()
 }
 
 
+pub fn encode_utc_time(tag:u8,value:& UtcTime,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
+    encode_octet_string(tag,&value.to_bytes(),buffer,pos,fill)
+}
+
 pub fn encode_interger_general(tag:u8,value:  &[u8],buffer: &mut[u8],pos:usize,fill:bool) ->usize{
-    
-    compress_interger(tag,value,buffer,pos,fill)      
+
+    compress_interger(tag,value,buffer,pos,fill)
 }
 
 pub fn encode_unsigned_general(tag:u8,value:  &[u8],buffer: &mut[u8],pos:usize,fill:bool) ->usize{
     let prepend=[&[0x00 as u8], value].concat();
-    compress_interger(tag,&prepend,buffer,pos,fill)      
+    compress_interger(tag,&prepend,buffer,pos,fill)
 }
 
 pub fn encode_interger(tag:u8,value: i32,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
     let compressed = value.to_be_bytes();
-    compress_interger(tag,&compressed,buffer,pos,fill)      
+    compress_interger(tag,&compressed,buffer,pos,fill)
 }
 
 pub fn encode_unsigned(tag:u8,value: u32,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
@@ -89,59 +72,30 @@ pub fn compress_interger(tag:u8,compressed: &[u8],buffer: &mut[u8],pos:usize,fil
     }
     //println!("{:?},{}",compressed,compress_start);
 
-    let compressed_size=compressed.len()-compress_start;
-
-    if !fill{
-        return compressed_size;
-    }
-
-    let mut new_pos=pos;
-   
-    buffer[new_pos]=tag;
-    new_pos+=1;
-    buffer[new_pos]=compressed_size as u8; //?
-    new_pos+=1;
-
-    buffer[new_pos..new_pos+compressed_size].copy_from_slice(&compressed[compress_start..]);
-    new_pos+compressed_size
+    encode_octet_string(tag,&compressed[compress_start..],buffer,pos,fill)
 }
 pub fn encode_float_general(tag:u8,bytes:&[u8],buffer: &mut[u8],pos:usize,fill:bool) ->usize{
-    
+
     if !fill{
         return bytes.len()+1;
     }
 
-    let mut new_pos=pos;
-    buffer[new_pos]=tag;
-    new_pos+=1;
-    buffer[new_pos]=(bytes.len()+1) as u8; //?
+    let mut new_pos=encode_tag_length(tag,bytes.len()+1,buffer,pos,fill);
+    buffer[new_pos]=float_exponent_width(bytes.len()); //exponent
     new_pos+=1;
-    buffer[new_pos]=0x08; //exponent
-    new_pos+=1;
-    buffer[new_pos..new_pos+bytes.len()].copy_from_slice(&bytes);
+    buffer[new_pos..new_pos+bytes.len()].copy_from_slice(bytes);
 
     new_pos+=bytes.len();
     new_pos
 }
 
-pub fn encode_float(tag:u8,value:f32,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
-    let bytes=value.to_be_bytes();
-    
-    if !fill{
-        return bytes.len()+1;
-    }
-
-    let mut new_pos=pos;
-    buffer[new_pos]=tag;
-    new_pos+=1;
-    buffer[new_pos]=(bytes.len()+1) as u8; //?
-    new_pos+=1;
-    buffer[new_pos]=0x08; //exponent
-    new_pos+=1;
-    buffer[new_pos..new_pos+bytes.len()].copy_from_slice(&bytes);
+// IEC 61850-8-1 FloatingPoint: the octet in front of the IEEE 754 value gives the exponent width
+pub fn float_exponent_width(octets: usize) ->u8{
+    if octets==8 {0x0b} else {0x08}
+}
 
-    new_pos+=bytes.len();
-    new_pos
+pub fn encode_float(tag:u8,value:f32,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
+    encode_float_general(tag,&value.to_be_bytes(),buffer,pos,fill)
 }
 
 pub fn encode_bit_string(tag:u8,value:& [u8],padding: u8,buffer: &mut[u8],pos:usize,fill:bool) ->usize{
@@ -151,18 +105,13 @@ pub fn encode_bit_string(tag:u8,value:& [u8],padding: u8,buffer: &mut[u8],pos:us
         return value.len()+1;
     }
 
-    let mut new_pos=pos;
-
-    buffer[new_pos]=tag;
-    new_pos+=1;
-    buffer[new_pos]=(value.len()+1) as u8; //?
-    new_pos+=1;
+    let mut new_pos=encode_tag_length(tag,value.len()+1,buffer,pos,fill);
 
     buffer[new_pos]=padding;
     new_pos+=1;
 
-    for i in 0..value.len()  {
-        buffer[new_pos+i]=value[value.len()-i-1].reverse_bits();
+    for (i, byte) in value.iter().rev().enumerate() {
+        buffer[new_pos+i]=byte.reverse_bits();
     }
     new_pos+value.len()
 }
@@ -176,32 +125,17 @@ pub fn encode_tag_length(tag:u8,value: usize,buffer: &mut[u8],pos:usize,fill:boo
     buffer[new_pos]=tag;
     new_pos+=1;
 
-    if value<128 {
+    let octets=size_length(value);
+    if octets==1 {
         buffer[new_pos]=value as u8;
-        new_pos+=1;
-    }
-    else if value<256 {
-        buffer[new_pos]=0x81;
-        new_pos+=1;  
-        buffer[new_pos]=value as u8;
-        new_pos+=1;       
-    }
-    else if value<65535 {
-        buffer[new_pos]=0x82;
-        new_pos+=1;  
-        buffer[new_pos]=(value/256) as u8;
-        new_pos+=1;     
-        buffer[new_pos]=(value&0xff) as u8;
-        new_pos+=1;     
+        return new_pos+1;
     }
-    else {
-        buffer[new_pos]=0x83;
-        new_pos+=1;  
-        buffer[new_pos]=(value/0x10000) as u8;
-        new_pos+=1;     
-        buffer[new_pos]=((value& 0xffff) / 0x100) as u8;
-        new_pos+=1;     
-        buffer[new_pos]=(value&0xff) as u8;
+
+    // long form, 0x80 | number of length octets followed by the length big endian
+    buffer[new_pos]=0x80 | (octets-1) as u8;
+    new_pos+=1;
+    for i in (0..octets-1).rev() {
+        buffer[new_pos]=(value >> (8*i)) as u8;
         new_pos+=1;
     }
 
@@ -210,16 +144,60 @@ pub fn encode_tag_length(tag:u8,value: usize,buffer: &mut[u8],pos:usize,fill:boo
 
 pub fn size_length(value: usize) ->usize
 {
-    if value<128 {
-        return 1;
+    if value<0x80 {
+        1
     }
-    else if value<256 {
-        return 2;
+    else if value<0x100 {
+        2
     }
-    else if value<65535 {
-        return 3;
+    else if value<0x10000 {
+        3
     }
-    else {       
-        return 4;
+    else {
+        4
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_decoder::decode_tag_length;
+
+    fn round_trip(length: usize, buffer: &mut [u8]) -> usize{
+        let size = 1 + encode_tag_length(0x8a, length, buffer, 0, false);
+        let end = encode_tag_length(0x8a, length, buffer, 0, true);
+        assert_eq!(end, size, "size of length {}", length);
+        assert_eq!(end, 1 + size_length(length), "size of length {}", length);
+
+        let mut tag = 0;
+        let mut value = 0;
+        let pos = decode_tag_length(&mut tag, &mut value, buffer, 0).unwrap();
+        assert_eq!((pos, tag, value), (end, 0x8a, length), "length {}", length);
+        end
+    }
+
+    #[test]
+    fn tag_length_boundaries(){
+        let mut buffer = vec![0_u8;0x10000 + 8];
+        let expected: [(usize, &[u8]);6] = [
+            (127, &[0x7f]),
+            (128, &[0x81, 0x80]),
+            (255, &[0x81, 0xff]),
+            (256, &[0x82, 0x01, 0x00]),
+            (65535, &[0x82, 0xff, 0xff]),
+            (65536, &[0x83, 0x01, 0x00, 0x00]),
+        ];
+        for (length, octets) in expected{
+            let end = round_trip(length, &mut buffer);
+            assert_eq!(&buffer[1..end], octets, "length {}", length);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tag_length_round_trip(){
+        let mut buffer = vec![0_u8;0x20000 + 8];
+        for length in 0..0x20000{
+            round_trip(length, &mut buffer);
+        }
+    }
+}
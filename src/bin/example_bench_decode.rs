@@ -0,0 +1,104 @@
+extern crate goose_packet;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,VLANTag,IECData};
+use goose_packet::pdu::{encodeGoosePacket,sizeGoosePacket,getTimeMs,decodeGoosePacket};
+use goose_packet::pdu_ref::{decodeGoosePacketRef,IECDataRef,IECDataIter};
+
+// counts heap allocations so the borrowed path can show it does none
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// touches every element like a probe would, returns the number of leaves
+fn walk(iter: IECDataIter) -> usize{
+    let mut leaves = 0;
+    for data in iter{
+        match data{
+            Ok(IECDataRef::array(nested)) | Ok(IECDataRef::structure(nested)) => leaves += walk(nested),
+            Ok(_) => leaves += 1,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    leaves
+}
+
+fn main(){
+    let iterations: usize = std::env::args().nth(1).and_then(|arg| arg.parse().ok()).unwrap_or(200_000);
+
+    let mut data = vec![];
+    for i in 0..8{
+        data.push(IECData::structure(vec![
+            IECData::boolean(i%2 == 0),
+            IECData::bit_string{ padding: 6, val: vec![0x80, 0x00] },
+            IECData::utc_time(getTimeMs()),
+        ]));
+        data.push(IECData::float32(i as f32 * 0.5));
+        data.push(IECData::visible_string(format!("value{}", i)));
+    }
+    let packet = IECGoosePacket{
+        eth_hdr: EthernetHeader{ srcAddr:[0x00,0x01,0x02,0x03,0x04,0x05], dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01], VLAN: Some(VLANTag::new(1)), outerVLAN: None, HSR: None },
+        goose_hdr: IECGooseHeader{ APPID:[0x01,0x01], ..Default::default() },
+        pdu: IECGoosePdu{
+            gocbRef:"IED1LD0/LLN0$GO$gcb1".to_string(),
+            timeAllowedtoLive:2000,
+            datSet:"IED1LD0/LLN0$ds1".to_string(),
+            goID:"IED1gcb1".to_string(),
+            t:getTimeMs(),
+            stNum:1,
+            sqNum:0,
+            simulation:false,
+            confRev:1,
+            ndsCom:false,
+            numDatSetEntries:data.len() as u32,
+            allData:data,
+        },
+        prp: None,
+    };
+    let mut buffer = vec![0_u8;sizeGoosePacket(&packet)];
+    encodeGoosePacket(&packet, &mut buffer, 0).unwrap();
+
+    // both paths have to agree before timing them
+    let owned = decodeGoosePacket(&buffer, 0).unwrap().unwrap();
+    let borrowed = decodeGoosePacketRef(&buffer, 0).unwrap().unwrap();
+    assert_eq!(format!("{:?}", owned.pdu), format!("{:?}", borrowed.pdu.to_owned_pdu().unwrap()));
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut checksum = 0;
+    for _ in 0..iterations{
+        let pkt = decodeGoosePacket(&buffer, 0).unwrap().unwrap();
+        checksum += pkt.pdu.stNum as usize + pkt.pdu.allData.len();
+    }
+    let owned_time = start.elapsed();
+    let owned_allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..iterations{
+        let pkt = decodeGoosePacketRef(&buffer, 0).unwrap().unwrap();
+        checksum += pkt.pdu.stNum as usize + walk(pkt.pdu.allData());
+    }
+    let borrowed_time = start.elapsed();
+    let borrowed_allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!("{} frames of {} bytes (checksum {})", iterations, buffer.len(), checksum);
+    println!("owned    {:>8.0} ns/frame {:>6.1} allocations/frame", owned_time.as_nanos() as f64 / iterations as f64, owned_allocations as f64 / iterations as f64);
+    println!("borrowed {:>8.0} ns/frame {:>6.1} allocations/frame", borrowed_time.as_nanos() as f64 / iterations as f64, borrowed_allocations as f64 / iterations as f64);
+}
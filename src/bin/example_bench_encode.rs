@@ -0,0 +1,118 @@
+#![allow(non_snake_case)]
+
+extern crate goose_packet;
+
+use std::time::{Duration, Instant};
+
+use goose_packet::types::{IECGoosePacket,IECGooseHeader,EthernetHeader,VLANTag,IECGoosePdu,IECData};
+use goose_packet::pdu::{getTimeMs,encodeGoosePacket,sizeGoosePacket,decodeGoosePacket};
+use goose_packet::pdu_encoder::{encodeIECGoosePdu,sizeGoosePduFrame};
+use goose_packet::stream_encoder::{GooseSizeTree,writeIECGoosePdu,encodeIECGoosePduToSlice};
+use goose_packet::frame_template::GooseFrameTemplate;
+
+// structure nested depth levels deep, every level carrying a few leaves
+fn nested(depth: usize) -> IECData{
+    let mut data = vec![
+        IECData::boolean(depth.is_multiple_of(2)),
+        IECData::int32(depth as i32 * 1000),
+        IECData::bit_string{ padding: 3, val: vec![0x40, 0x00] },
+    ];
+    if depth > 0{
+        data.push(nested(depth - 1));
+    }
+    IECData::structure(data)
+}
+
+fn pdu(depth: usize) -> IECGoosePdu{
+    let allData: Vec<IECData> = (0..4).map(|_| nested(depth)).collect();
+    IECGoosePdu{
+        gocbRef:"IED1LD0/LLN0$GO$gcb1".to_string(),
+        timeAllowedtoLive:2000,
+        datSet:"IED1LD0/LLN0$ds1".to_string(),
+        goID:"IED1gcb1".to_string(),
+        t:getTimeMs(),
+        stNum:1,
+        sqNum:0,
+        simulation:false,
+        confRev:1,
+        ndsCom:false,
+        numDatSetEntries:allData.len() as u32,
+        allData,
+    }
+}
+
+fn perFrame(elapsed: Duration, iterations: usize) -> f64{
+    elapsed.as_nanos() as f64 / iterations as f64
+}
+
+fn main(){
+    let iterations: usize = std::env::args().nth(1).and_then(|arg| arg.parse().ok()).unwrap_or(20_000);
+
+    println!("{:>5} {:>6} {:>12} {:>12} {:>12}", "depth", "bytes", "two-pass", "slice", "vec");
+    for depth in [1, 4, 8, 16, 32]{
+        let pdu = pdu(depth);
+        let size = sizeGoosePduFrame(&pdu);
+
+        // all writers have to produce the same bytes before timing them
+        let mut reference = vec![0_u8;size];
+        encodeIECGoosePdu(&pdu, &mut reference, 0).unwrap();
+        let mut slice = vec![0_u8;size];
+        assert_eq!(encodeIECGoosePduToSlice(&pdu, &mut slice, 0).unwrap(), size);
+        let mut vec = Vec::new();
+        writeIECGoosePdu(&pdu, &GooseSizeTree::new(&pdu), &mut vec).unwrap();
+        assert_eq!(reference, slice);
+        assert_eq!(reference, vec);
+
+        let start = Instant::now();
+        for _ in 0..iterations{
+            encodeIECGoosePdu(&pdu, &mut reference, 0).unwrap();
+        }
+        let two_pass = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..iterations{
+            encodeIECGoosePduToSlice(&pdu, &mut slice, 0).unwrap();
+        }
+        let single_pass = start.elapsed();
+
+        // a publisher keeps the tree and the buffer between frames
+        let mut tree = GooseSizeTree::default();
+        let start = Instant::now();
+        for _ in 0..iterations{
+            vec.clear();
+            tree.update(&pdu);
+            writeIECGoosePdu(&pdu, &tree, &mut vec).unwrap();
+        }
+        let streamed = start.elapsed();
+
+        println!("{:>5} {:>6} {:>9.0} ns {:>9.0} ns {:>9.0} ns", depth, size, perFrame(two_pass, iterations), perFrame(single_pass, iterations), perFrame(streamed, iterations));
+    }
+
+    // retransmissions of an unchanged dataset: full encode against patching the template
+    let mut packet = IECGoosePacket{
+        eth_hdr: EthernetHeader{ srcAddr:[0x00,0x01,0x02,0x03,0x04,0x05], dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01], VLAN: Some(VLANTag::new(1)), outerVLAN: None, HSR: None },
+        goose_hdr: IECGooseHeader{ APPID:[0x01,0x01], ..Default::default() },
+        pdu: pdu(8),
+        prp: None,
+    };
+    let mut buffer = vec![];
+    let mut template = GooseFrameTemplate::new(&packet).unwrap();
+
+    let start = Instant::now();
+    for sqNum in 0..iterations as u32{
+        packet.pdu.sqNum = sqNum;
+        buffer.resize(sizeGoosePacket(&packet), 0);
+        encodeGoosePacket(&packet, &mut buffer, 0).unwrap();
+    }
+    let encoded = start.elapsed();
+
+    let start = Instant::now();
+    for sqNum in 0..iterations as u32{
+        template.update(packet.pdu.stNum, sqNum, &packet.pdu.t);
+    }
+    let patched = start.elapsed();
+
+    let pkt = decodeGoosePacket(template.frame(), 0).unwrap().unwrap();
+    assert_eq!(pkt.pdu.sqNum, iterations as u32 - 1);
+    println!("retransmission {:>9.0} ns encoded {:>9.0} ns patched", perFrame(encoded, iterations), perFrame(patched, iterations));
+}
@@ -0,0 +1,62 @@
+extern crate goose_packet;
+
+use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,VLANTag,IECData};
+use goose_packet::pdu::{encodeGoosePacket,sizeGoosePacket,decodeGoosePacket,getTimeMs};
+use goose_packet::frame_template::GooseFrameTemplate;
+use goose_packet::conformance::{lintGoosePacket,decodeGoosePacketStrict};
+
+fn report(name: &str, frame: &[u8]){
+    let violations = lintGoosePacket(frame, 0).unwrap().unwrap();
+    println!("{}: {} deviations", name, violations.len());
+    for violation in violations.iter(){
+        println!("\t{:?} {}", violation.kind, violation);
+    }
+    println!("\tlenient {}", if decodeGoosePacket(frame, 0).unwrap().is_ok() {"accepted"} else {"rejected"});
+    match decodeGoosePacketStrict(frame, 0).unwrap(){
+        Ok(_) => println!("\tstrict accepted"),
+        Err(e) => println!("\tstrict rejected: {} at {}", e.message, e.pos),
+    }
+}
+
+fn main(){
+    let data = vec![
+        IECData::boolean(true),
+        IECData::float64(50.0),
+        IECData::structure(vec![IECData::int32u(200), IECData::bit_string{ padding: 3, val: vec![0x00, 0x40] }]),
+    ];
+    let packet = IECGoosePacket{
+        eth_hdr: EthernetHeader{ srcAddr:[0x00,0x01,0x02,0x03,0x04,0x05], dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01], VLAN: Some(VLANTag::new(1)), outerVLAN: None, HSR: None },
+        goose_hdr: IECGooseHeader{ APPID:[0x01,0x01], ..Default::default() },
+        pdu: IECGoosePdu{
+            gocbRef:"IED1LD0/LLN0$GO$gcb1".to_string(),
+            timeAllowedtoLive:2000,
+            datSet:"IED1LD0/LLN0$ds1".to_string(),
+            goID:"IED1gcb1".to_string(),
+            t:getTimeMs(),
+            stNum:1,
+            sqNum:0,
+            simulation:false,
+            confRev:1,
+            ndsCom:false,
+            numDatSetEntries:data.len() as u32,
+            allData:data,
+        },
+        prp: None,
+    };
+
+    let mut frame = vec![0_u8;sizeGoosePacket(&packet)];
+    encodeGoosePacket(&packet, &mut frame, 0).unwrap();
+    report("encoder", &frame);
+
+    // fixed width stNum and sqNum
+    let template = GooseFrameTemplate::new(&packet).unwrap();
+    report("template", template.frame());
+
+    // what other stacks send: boolean 0x01, float64 with exponent width 8
+    let mut foreign = frame.clone();
+    let boolean = foreign.windows(3).position(|window| window == [0x83, 0x01, 0xff]).unwrap();
+    foreign[boolean + 2] = 0x01;
+    let float = foreign.windows(3).position(|window| window == [0x87, 0x09, 0x0b]).unwrap();
+    foreign[float + 2] = 0x08;
+    report("foreign", &foreign);
+}
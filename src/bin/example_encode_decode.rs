@@ -1,20 +1,23 @@
 extern crate goose_packet;
 
-use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,IECData};
-use goose_packet::pdu::{encodeGoosePacket,getTimeMs,display_buffer,decodeGoosePacket};
-
-const GOOSE_BUFFER_SIZE:usize = 512;
+use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,VLANTag,IECData};
+use goose_packet::pdu::{encodeGoosePacket,sizeGoosePacket,getTimeMs,display_buffer,decodeGoosePacket};
 
 fn main(){
 
     let ether_header= EthernetHeader{
         srcAddr:[00 as u8;6],
         dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01],
-        VLANID: Some(0x8001),
+        VLAN: Some(VLANTag::new(1)),
+        outerVLAN: None,
+        HSR: None,
     };
     let goose_header = IECGooseHeader{
         APPID:[0x01,0x01],
         length:0,
+        simulation:false,
+        security_length:0,
+        reserved2:0,
     };
     let current_time=getTimeMs();
     let goose_data=vec![
@@ -30,6 +33,7 @@ fn main(){
         IECData::structure(
             vec![
                 IECData::int32u(4294967295),
+                IECData::int64u(18446744073709551615),
                 IECData::float32(0.123),
                 IECData::octet_string(vec![0x22,0x33,0x66]),
                 IECData::utc_time(current_time)
@@ -55,8 +59,6 @@ fn main(){
         allData:goose_data,
         };
 
-    let mut buffer=[0 as u8;GOOSE_BUFFER_SIZE];
-
         let goose_packet = IECGoosePacket{
         eth_hdr: ether_header,
         goose_hdr: goose_header,
@@ -64,7 +66,15 @@ fn main(){
         prp: None
     };
 
-    let goose_frame_size=encodeGoosePacket(&goose_packet,&mut buffer,0);
+    let mut buffer=vec![0_u8;sizeGoosePacket(&goose_packet)];
+
+    let goose_frame_size=match encodeGoosePacket(&goose_packet,&mut buffer,0){
+        Ok(size) => size,
+        Err(e) => {
+            eprintln!("Error encoding goose frame {} at posistion {}", e.message, e.pos);
+            return;
+        }
+    };
 
     println!("goose frame:");
     display_buffer(&buffer,goose_frame_size);
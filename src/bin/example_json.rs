@@ -16,6 +16,7 @@ fn main(){
         IECData::structure(
             vec![
                 IECData::int32u(4294967295),
+                IECData::int64u(18446744073709551615),
                 IECData::float32(0.123),
                 IECData::octet_string(vec![0x22,0x33,0x66]),
                 IECData::utc_time(current_time)
@@ -0,0 +1,72 @@
+extern crate goose_packet;
+
+use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,VLANTag,IECData};
+use goose_packet::pdu::getTimeMs;
+use goose_packet::pcap::{PcapReader,PcapWriter};
+use goose_packet::publisher::{GoosePublisher,RetransmissionCurve,SystemClock};
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader,BufWriter};
+
+// records a few published frames into a pcap file Wireshark can open
+fn write_sample(path: &str){
+    let goose_data=vec![IECData::boolean(false), IECData::int32(0)];
+    let goose_packet = IECGoosePacket{
+        eth_hdr: EthernetHeader{ srcAddr:[0x00,0x01,0x02,0x03,0x04,0x05], dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01], VLAN: Some(VLANTag::new(1)), outerVLAN: None, HSR: None },
+        goose_hdr: IECGooseHeader{ APPID:[0x01,0x01], ..Default::default() },
+        pdu: IECGoosePdu{
+            gocbRef:"testGoose".to_string(),
+            timeAllowedtoLive:0,
+            datSet:"test_datSet".to_string(),
+            goID:"test_ID".to_string(),
+            t:getTimeMs(),
+            stNum:1,
+            sqNum:0,
+            simulation:false,
+            confRev:1,
+            ndsCom:false,
+            numDatSetEntries:goose_data.len() as u32,
+            allData:goose_data,
+        },
+        prp: None,
+    };
+
+    let file = File::create(path).expect("create capture file");
+    let writer = PcapWriter::new(BufWriter::new(file)).expect("write pcap header");
+    let mut publisher = GoosePublisher::new(goose_packet, RetransmissionCurve::default(), SystemClock, writer);
+    publisher.poll().expect("publish");
+    publisher.update(vec![IECData::boolean(true), IECData::int32(1)]).expect("publish state change");
+    publisher.sink_mut().flush().expect("flush capture file");
+    println!("wrote {}", path);
+}
+
+fn main(){
+    let path = match env::args().nth(1){
+        Some(path) => path,
+        None => {
+            let path = "goose.pcap".to_string();
+            write_sample(&path);
+            path
+        }
+    };
+
+    let file = File::open(&path).expect("open capture file");
+    let reader = match PcapReader::new(BufReader::new(file)){
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("Error reading {}: {} at posistion {}", path, e.message, e.pos);
+            return;
+        }
+    };
+    for result in reader.goose_packets(){
+        match result {
+            Ok((timestamp, pkt)) => {
+                println!("{:?} Goose packet {:?}", timestamp, pkt);
+            },
+            Err(e) => {
+                eprintln!("Error parsing goose fraame {} at posistion {}", e.message, e.pos);
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+extern crate goose_packet;
+
+use pnet::datalink::{self,interfaces,Channel, NetworkInterface};
+use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,VLANTag,IECData};
+use goose_packet::pdu::getTimeMs;
+use goose_packet::publisher::{GoosePublisher,RetransmissionCurve,SystemClock};
+use goose_packet::error::GooseError;
+
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn display_network_interfaces(){
+    let interfaces = interfaces();
+    for interface in interfaces.iter() {
+        println!("interface  {}", interface.index);
+        println!("\t name {}", interface.name);
+        println!("\t ips {:?}", interface.ips);
+        println!("\t description {}", interface.description);
+
+    }
+}
+
+fn main(){
+    let interface_name = match env::args().nth(1){
+        Some (name)=>name,
+        None=>{
+            println!("please add an interface name as argument. the available interface in the system:");
+            display_network_interfaces();
+            panic!();
+        }
+    };
+
+    let interface_names_match =
+        |iface: &NetworkInterface| iface.name == interface_name;
+    let interfaces = interfaces();
+    // Find the network interface with the provided name
+    let interface = match interfaces.into_iter()
+                              .find(interface_names_match) {
+        Some(val)=>val,
+        _=>{
+            println!("unknown interface name. the available interface in the system:");
+            display_network_interfaces();
+            panic!();
+        }
+
+    };
+
+    let (mut tx, _) = match datalink::channel(&interface, Default::default()) {
+        Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+        Ok(_) => panic!("Unknown channel type"),
+        Err(e) => panic!("Error happened {}", e),
+    };
+
+    let goose_data=vec![
+        IECData::boolean(false),
+        IECData::int32(0),
+        ];
+    let goose_pdu= IECGoosePdu{
+        gocbRef:"testGoose".to_string(),
+        timeAllowedtoLive:0,
+        datSet:"test_datSet".to_string(),
+        goID:"test_ID".to_string(),
+        t:getTimeMs(),
+        stNum:1,
+        sqNum:0,
+        simulation:false,
+        confRev:1,
+        ndsCom:false,
+        numDatSetEntries:goose_data.len() as u32,
+        allData:goose_data,
+        };
+    let goose_packet = IECGoosePacket{
+        eth_hdr: EthernetHeader{ srcAddr:[00_u8;6], dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01], VLAN: Some(VLANTag::new(1)), outerVLAN: None, HSR: None },
+        goose_hdr: IECGooseHeader{ APPID:[0x01,0x01], ..Default::default() },
+        pdu: goose_pdu,
+        prp: None,
+    };
+
+    let sink = move |frame: &[u8]| -> Result<(),GooseError>{
+        match tx.send_to(frame,None){
+            Some(Ok(())) => Ok(()),
+            Some(Err(e)) => Err(GooseError{ message: e.to_string(), pos: 0 }),
+            None => Err(GooseError{ message: "send buffer full".into(), pos: 0 }),
+        }
+    };
+    let mut publisher = GoosePublisher::new(goose_packet, RetransmissionCurve::default(), SystemClock, sink);
+
+    // toggle the first dataset entry every 5 seconds, retransmit in between
+    let start = Instant::now();
+    let mut changes = 0;
+    loop {
+        if start.elapsed() >= Duration::from_secs(5 * (changes + 1)){
+            changes += 1;
+            publisher.update(vec![IECData::boolean(changes % 2 == 1), IECData::int32(changes as i32)]).expect("publish state change");
+            println!("state change stNum {}", publisher.packet().pdu.stNum);
+        }
+        let wait = publisher.poll().expect("publish retransmission");
+        thread::sleep(wait.min(Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,34 @@
+extern crate goose_packet;
+
+use std::time::{Duration, SystemTime};
+
+use goose_packet::types::{IECGoosePacket,IECPRP1,IECPRPLAN};
+use goose_packet::redundancy::DuplicateDiscard;
+
+fn main(){
+
+    let mut discard = DuplicateDiscard::new(64, Duration::from_millis(400));
+    let start = SystemTime::now();
+
+    let mut pkt = IECGoosePacket::default();
+    pkt.eth_hdr.srcAddr = [0x00,0x01,0x02,0x03,0x04,0x05];
+    pkt.pdu.gocbRef = "testGoose".to_string();
+
+    // LAN A loses sequence 3, LAN B loses 6 and swaps 4 and 5
+    let lan_a = [0,1,2,4,5,6,7,8,9];
+    let lan_b = [0,1,2,3,5,4,7,8,9];
+
+    for (i, (a, b)) in lan_a.iter().zip(lan_b.iter()).enumerate(){
+        let now = start + Duration::from_millis(i as u64);
+        for (lan, sequence) in [(IECPRPLAN::LAN_A, *a), (IECPRPLAN::LAN_B, *b)]{
+            pkt.prp = Some(IECPRP1{ sequence, lan, frame_size: 0 });
+            let result = discard.on_packet(&pkt, now);
+            println!("{:?} sequence {} -> {:?}", lan, sequence, result);
+        }
+    }
+
+    discard.expire(start + Duration::from_secs(1));
+
+    println!("LAN A {:?}", discard.statistics(IECPRPLAN::LAN_A));
+    println!("LAN B {:?}", discard.statistics(IECPRPLAN::LAN_B));
+}
@@ -0,0 +1,93 @@
+extern crate goose_packet;
+
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use goose_packet::types::{IECGoosePdu,IECData};
+use goose_packet::pdu::{getTimeMs,display_buffer};
+use goose_packet::rgoose::{IECRGoosePacket,RGooseSecurityInfo,RGOOSE_PORT,encodeRGoosePacket,sizeRGoosePacket,decodeRGoosePacket};
+
+fn main(){
+
+    // port 102 needs privileges on most systems, fall back to any free port for the demo
+    let subscriber = match UdpSocket::bind(("127.0.0.1", RGOOSE_PORT)){
+        Ok(socket) => socket,
+        Err(_) => UdpSocket::bind("127.0.0.1:0").expect("bind subscriber socket"),
+    };
+    subscriber.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    let publisher = UdpSocket::bind("127.0.0.1:0").expect("bind publisher socket");
+    publisher.connect(subscriber.local_addr().unwrap()).expect("connect publisher socket");
+    println!("publishing from {} to {}", publisher.local_addr().unwrap(), subscriber.local_addr().unwrap());
+
+    let mut pkt = IECRGoosePacket{
+        spduNumber: 0,
+        version: 1,
+        security: RGooseSecurityInfo::default(),
+        simulation: false,
+        APPID: [0x00,0x01],
+        pdu: IECGoosePdu{
+            gocbRef:"IED1LD0/LLN0$GO$gcb1".to_string(),
+            timeAllowedtoLive:2000,
+            datSet:"IED1LD0/LLN0$ds1".to_string(),
+            goID:"rgoose1".to_string(),
+            t:getTimeMs(),
+            stNum:1,
+            sqNum:0,
+            simulation:false,
+            confRev:1,
+            ndsCom:false,
+            numDatSetEntries:1,
+            allData:vec![IECData::boolean(false)],
+        },
+        signature: vec![],
+    };
+
+    let mut send_buffer = vec![];
+    let mut receive_buffer = [0_u8;65536];
+    for i in 0..4{
+        // every other message is a state change, the rest retransmissions
+        if i%2 == 0 && i > 0{
+            pkt.pdu.stNum += 1;
+            pkt.pdu.sqNum = 0;
+            pkt.pdu.allData = vec![IECData::boolean(i%4 == 2)];
+            pkt.pdu.t = getTimeMs();
+        }
+        else if i > 0{
+            pkt.pdu.sqNum += 1;
+        }
+        pkt.spduNumber = i;
+
+        send_buffer.resize(sizeRGoosePacket(&pkt), 0);
+        let size = match encodeRGoosePacket(&pkt, &mut send_buffer, 0){
+            Ok(size) => size,
+            Err(e) => {
+                eprintln!("Error encoding R-GOOSE SPDU {} at posistion {}", e.message, e.pos);
+                return;
+            }
+        };
+        if i == 0{
+            println!("R-GOOSE SPDU:");
+            display_buffer(&send_buffer, size);
+        }
+        publisher.send(&send_buffer[..size]).expect("send SPDU");
+
+        let (received, from) = match subscriber.recv_from(&mut receive_buffer){
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Error receiving R-GOOSE SPDU {}", e);
+                return;
+            }
+        };
+        match decodeRGoosePacket(&receive_buffer[..received], 0){
+            Some(Ok(rx)) => {
+                println!("from {} SPDU {} stNum {} sqNum {} data {:?}", from, rx.spduNumber, rx.pdu.stNum, rx.pdu.sqNum, rx.pdu.allData);
+            },
+            Some(Err(e)) => {
+                eprintln!("Error parsing R-GOOSE SPDU {} at posistion {}", e.message, e.pos);
+            },
+            None => {
+                eprintln!("not a GOOSE SPDU");
+            }
+        }
+    }
+}
@@ -2,8 +2,10 @@ extern crate goose_packet;
 
 use pnet::datalink::{self,interfaces,Channel, NetworkInterface};
 use goose_packet::pdu::decodeGoosePacket;
+use goose_packet::subscriber::GooseSubscriber;
 
 use std::env;
+use std::time::SystemTime;
 
 fn display_network_interfaces(){
     let interfaces = interfaces();
@@ -50,6 +52,8 @@ fn main(){
 
     println!("start listening goose messages");
 
+    let mut subscriber = GooseSubscriber::new();
+
     loop {
         match rx.next() {
             Ok(packet) => {
@@ -60,6 +64,10 @@ fn main(){
                     match result {
                         Ok(pkt) =>{
                             println!("Goose packet {:?}",pkt);
+                            let now = SystemTime::now();
+                            for event in subscriber.check_timeouts(now).iter().chain(subscriber.on_packet(&pkt, now).iter()){
+                                println!("Goose event {:?}",event);
+                            }
                         },
                         Err(e) =>{
                             eprintln!("Error parsing goose fraame {} at posistion {}", e.message, e.pos);
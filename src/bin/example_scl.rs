@@ -0,0 +1,67 @@
+#![allow(non_snake_case)]
+
+extern crate goose_packet;
+
+use std::env;
+
+use goose_packet::scl::{parseScl,loadScl};
+use goose_packet::pdu::{encodeGoosePacket,sizeGoosePacket,decodeGoosePacket};
+use goose_packet::subscriber::GooseSubscriber;
+use goose_packet::types::IECData;
+use goose_packet::attributes::{Dbpos,Quality,Validity};
+use std::time::SystemTime;
+
+// a CID file as an engineering tool writes it, reduced to one breaker and its GOOSE
+const SAMPLE_CID: &str = include_str!("../scl_sample.cid");
+
+fn main(){
+    // an SCL file can be given as argument, otherwise the sample above is used
+    let controls = match env::args().nth(1){
+        Some(path) => loadScl(path),
+        None => parseScl(SAMPLE_CID),
+    }.unwrap();
+
+    let mut subscriber = GooseSubscriber::new();
+    for control in controls.iter(){
+        println!("{} APPID {:02x}{:02x} VLAN {:?} {:?}", control.packet.pdu.gocbRef, control.packet.goose_hdr.APPID[0], control.packet.goose_hdr.APPID[1], control.packet.eth_hdr.VLAN, control.curve);
+        for (fcda, data) in control.fcdas.iter().zip(control.packet.pdu.allData.iter()){
+            println!("\t{} {:?}", fcda, data);
+        }
+        control.subscribe(&mut subscriber);
+
+        // a publisher sets values by reference before encoding
+        let mut packet = control.packet.clone();
+        control.binding.set(&mut packet.pdu.allData, "XCBR1.Pos.stVal[ST]", Dbpos::On).unwrap();
+        control.binding.set(&mut packet.pdu.allData, "LD0/TripPTRC1.Tr.general[ST]", IECData::boolean(true)).unwrap();
+        control.binding.set(&mut packet.pdu.allData, "XCBR1.Pos.q[ST]", Quality{ validity: Validity::Questionable, oldData: true, ..Default::default() }).unwrap();
+        println!("\twrong type: {:?}", control.binding.set(&mut packet.pdu.allData, "XCBR1.Pos.stVal[ST]", IECData::boolean(true)));
+
+        // the template encodes as it is
+        let mut buffer = vec![0_u8;sizeGoosePacket(&packet)];
+        encodeGoosePacket(&packet, &mut buffer, 0).unwrap();
+        let pkt = decodeGoosePacket(&buffer, 0).unwrap().unwrap();
+        println!("\t{} bytes, {} entries decoded", buffer.len(), pkt.pdu.allData.len());
+
+        // and a subscriber reads them back by the same references
+        let binding = &control.binding;
+        if let Some(stVal) = binding.get(&pkt.pdu.allData, "XCBR1.Pos.stVal[ST]").and_then(IECData::as_dbpos){
+            println!("\tstVal {}", stVal);
+        }
+        if let Some(q) = binding.get(&pkt.pdu.allData, "XCBR1.Pos.q[ST]").and_then(IECData::as_quality){
+            println!("\tq {}", q);
+        }
+        if let Some(t) = binding.get(&pkt.pdu.allData, "XCBR1.Pos.t[ST]").and_then(IECData::as_timestamp){
+            println!("\tt {}", t);
+        }
+        println!("\tgeneral {:?}", binding.get(&pkt.pdu.allData, "TripPTRC1.Tr.general[ST]").and_then(IECData::as_bool));
+        println!("\tschema {:?}", control.schema().validate(&pkt.pdu));
+        println!("\t{:?}", subscriber.on_packet(&pkt, SystemTime::now()));
+
+        // a publisher whose dataset drifted from the configuration is rejected
+        let mut drifted = pkt.clone();
+        if let Some(first) = drifted.pdu.allData.first_mut(){
+            *first = IECData::boolean(true);
+        }
+        println!("\t{:?}", subscriber.on_packet(&drifted, SystemTime::now()));
+    }
+}
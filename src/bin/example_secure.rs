@@ -0,0 +1,79 @@
+extern crate goose_packet;
+
+use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,VLANTag,IECData};
+use goose_packet::pdu::{getTimeMs,display_buffer,decodeGoosePacket};
+use goose_packet::security::{GooseKey,GooseMacAlgorithm,encodeSecureGoosePacket,sizeSecureGoosePacket,decodeSecureGoosePacket};
+
+fn main(){
+
+    let goose_packet = IECGoosePacket{
+        eth_hdr: EthernetHeader{
+            srcAddr:[0_u8;6],
+            dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01],
+            VLAN: Some(VLANTag::new(1)),
+            outerVLAN: None,
+            HSR: None,
+        },
+        goose_hdr: IECGooseHeader{
+            APPID:[0x00,0x01],
+            length:0,
+            simulation:false,
+            security_length:0,
+            reserved2:0,
+        },
+        pdu: IECGoosePdu{
+            gocbRef:"testGoose".to_string(),
+            timeAllowedtoLive:2000,
+            datSet:"test_datSet".to_string(),
+            goID:"test_ID".to_string(),
+            t:getTimeMs(),
+            stNum:1,
+            sqNum:0,
+            simulation:false,
+            confRev:1,
+            ndsCom:false,
+            numDatSetEntries:1,
+            allData:vec![IECData::boolean(true)],
+        },
+        prp: None
+    };
+
+    let key = GooseKey{ algorithm: GooseMacAlgorithm::HmacSha256_128, key: b"substation key".to_vec() };
+
+    let mut buffer=vec![0_u8;sizeSecureGoosePacket(&goose_packet,&key)];
+    let goose_frame_size=match encodeSecureGoosePacket(&goose_packet,&key,&mut buffer,0){
+        Ok(size) => size,
+        Err(e) => {
+            eprintln!("Error encoding goose frame {} at posistion {}", e.message, e.pos);
+            return;
+        }
+    };
+
+    println!("authenticated goose frame:");
+    display_buffer(&buffer,goose_frame_size);
+
+    let right_key = |pkt: &IECGoosePacket| if pkt.goose_hdr.APPID == [0x00,0x01] {Some(key.clone())} else {None};
+    let wrong_key = |_: &IECGoosePacket| Some(GooseKey{ algorithm: GooseMacAlgorithm::HmacSha256_128, key: b"other key".to_vec() });
+
+    match decodeSecureGoosePacket(&buffer,0,&right_key){
+        Some(Ok(pkt)) => println!("verified gocbRef {} stNum {}", pkt.pdu.gocbRef, pkt.pdu.stNum),
+        Some(Err(e)) => eprintln!("verification failed: {}", e),
+        None => eprintln!("not a goose frame"),
+    }
+
+    if let Some(Err(e)) = decodeSecureGoosePacket(&buffer,0,&wrong_key){
+        println!("wrong key: {}", e);
+    }
+
+    // a flipped confRev bit has to be detected
+    let mut tampered=buffer.clone();
+    tampered[goose_frame_size-30]^=0x01;
+    if let Some(Err(e)) = decodeSecureGoosePacket(&tampered,0,&right_key){
+        println!("tampered frame: {}", e);
+    }
+
+    // receivers without security support skip the extension
+    if let Some(Ok(pkt)) = decodeGoosePacket(&buffer,0){
+        println!("plain decode gocbRef {} stNum {}", pkt.pdu.gocbRef, pkt.pdu.stNum);
+    }
+}
@@ -0,0 +1,69 @@
+extern crate goose_packet;
+
+use goose_packet::types::{IECSVPacket,IECSVPdu,IECSVAsdu,IECSVSample,IECSVHeader,EthernetHeader,VLANTag,IECPRP1,IECPRPLAN};
+use goose_packet::pdu::{encodeSVPacket,sizeSVPacket,getTimeMs,display_buffer,decodeSVPacket};
+
+fn main(){
+
+    let ether_header= EthernetHeader{
+        srcAddr:[0_u8;6],
+        dstAddr:[0x01,0x0C,0xCD,0x04,0x00,0x01],
+        VLAN: Some(VLANTag::new(1)),
+        outerVLAN: None,
+        HSR: None,
+    };
+    let sv_header = IECSVHeader{
+        APPID:[0x40,0x00],
+        length:0,
+        simulation:false,
+        security_length:0,
+        reserved2:0,
+    };
+
+    // 9-2LE: Ia, Ib, Ic, In, Va, Vb, Vc, Vn
+    let samples=[100,-50,-50,0,6350,-3175,-3175,0];
+    let sv_asdu= IECSVAsdu{
+        svID:"MU01".to_string(),
+        datSet:None,
+        smpCnt:1234,
+        confRev:1,
+        refrTm:Some(getTimeMs()),
+        smpSynch:2,
+        smpRate:None,
+        seqData:samples.iter().map(|value| IECSVSample{value:*value,quality:0}).collect(),
+        smpMod:None,
+    };
+    let sv_packet = IECSVPacket{
+        eth_hdr: ether_header,
+        sv_hdr: sv_header,
+        pdu: IECSVPdu{ noASDU:1, security:None, asdu:vec![sv_asdu] },
+        prp: Some(IECPRP1{ sequence:1, lan:IECPRPLAN::LAN_A, frame_size:0 }),
+    };
+
+    let mut buffer=vec![0_u8;sizeSVPacket(&sv_packet)];
+
+    let sv_frame_size=match encodeSVPacket(&sv_packet,&mut buffer,0){
+        Ok(size) => size,
+        Err(e) => {
+            eprintln!("Error encoding sv frame {} at posistion {}", e.message, e.pos);
+            return;
+        }
+    };
+
+    println!("sv frame:");
+    display_buffer(&buffer,sv_frame_size);
+
+    println!("decode as:");
+    if let Some(result) = decodeSVPacket(& buffer,0)
+    {
+        match result {
+            Ok(pkt) =>{
+                println!("Packet {:?}",pkt);
+            },
+            Err(e) =>{
+                eprintln!("Error parsing sv frame {} at posistion {}", e.message, e.pos);
+            }
+        }
+    }
+
+}
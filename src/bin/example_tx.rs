@@ -1,13 +1,11 @@
 extern crate goose_packet;
 
 use pnet::datalink::{self,interfaces,Channel, NetworkInterface};
-use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,IECData};
-use goose_packet::pdu::{encodeGoosePacket,getTimeMs,display_buffer};
+use goose_packet::types::{IECGoosePacket,IECGoosePdu,IECGooseHeader,EthernetHeader,VLANTag,IECData};
+use goose_packet::pdu::{encodeGoosePacket,sizeGoosePacket,getTimeMs,display_buffer};
 
 use std::env;
 
-const GOOSE_BUFFER_SIZE:usize = 512;
-
 fn display_network_interfaces(){
     let interfaces = interfaces();
     for interface in interfaces.iter() {
@@ -54,11 +52,16 @@ fn main(){
     let ether_header= EthernetHeader{
         srcAddr:[00 as u8;6],
         dstAddr:[0x01,0x0C,0xCD,0x01,0x00,0x01],
-        VLANID: Some(0x8001),
+        VLAN: Some(VLANTag::new(1)),
+        outerVLAN: None,
+        HSR: None,
     };
     let goose_header = IECGooseHeader{
         APPID:[0x01,0x01],
         length: 0,
+        simulation: false,
+        security_length: 0,
+        reserved2: 0,
     };
     let current_time=getTimeMs();
     let goose_data=vec![
@@ -100,9 +103,12 @@ fn main(){
         };
 
     goose_pdu.numDatSetEntries=goose_pdu.allData.len() as u32;
-    let mut buffer=[0 as u8;GOOSE_BUFFER_SIZE];
     let goose_packet = IECGoosePacket{ eth_hdr: ether_header, goose_hdr: goose_header, pdu: goose_pdu, prp: None};
-    let goose_frame_size=encodeGoosePacket(&goose_packet, &mut buffer,0);
+    let mut buffer=vec![0_u8;sizeGoosePacket(&goose_packet)];
+    let goose_frame_size=match encodeGoosePacket(&goose_packet, &mut buffer,0){
+        Ok(size) => size,
+        Err(e) => panic!("Error encoding goose frame {} at posistion {}", e.message, e.pos),
+    };
 
     display_buffer(&buffer,goose_frame_size);
 
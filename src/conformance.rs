@@ -0,0 +1,298 @@
+#![allow(non_snake_case)]
+
+// BER conformance of received goosePdus: the decoders accept any valid BER, this lists where a
+// frame deviates from the canonical (DER) encoding IEC 61850-8-1 test cases expect. frames from
+// GooseFrameTemplate are flagged on purpose, their stNum and sqNum keep a fixed width
+
+use std::fmt;
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::pdu::decodeGooseFrame;
+use crate::pdu_decoder::MAX_DATA_DEPTH;
+use crate::basic_decoder::{check_bounds,decode_tag_length};
+use crate::basic_encoder::{size_length,float_exponent_width};
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum BerViolationKind {
+    // long form length where a shorter form fits
+    NonMinimalLength,
+    // leading 0x00 or 0xff octet that only repeats the sign
+    NonMinimalInteger,
+    // boolean other than 0x00 or 0xff
+    InvalidBoolean,
+    // exponent width octet other than 8 for float32 or 11 for float64
+    FloatExponentWidth,
+    // unused bits of the last bit string octet not zero
+    BitStringPadding,
+    // unsigned value with the sign bit of its first octet set
+    NegativeUnsigned,
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct BerViolation {
+    pub kind: BerViolationKind,
+    // offset into the frame buffer
+    pub pos: usize,
+    pub message: String,
+}
+
+impl fmt::Display for BerViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "{} at {}", self.message, self.pos)
+    }
+}
+
+impl From<BerViolation> for GooseError {
+    fn from(violation: BerViolation) -> GooseError{
+        GooseError{ message: violation.message, pos: violation.pos }
+    }
+}
+
+// every deviation in a GOOSE frame, None when it is not GOOSE like decodeGoosePacket
+pub fn lintGoosePacket(buffer: &[u8], pos:usize) -> Option<Result<Vec<BerViolation>,GooseError>>{
+    match decodeGooseFrame(buffer, pos)?{
+        Ok((_, layout)) => Some(lintGoosePdu(buffer, layout.appid_pos + 8)),
+        Err(e) => Some(Err(e)),
+    }
+}
+
+// decodeGoosePacket that rejects frames with any deviation, the error reports the first one
+pub fn decodeGoosePacketStrict(buffer: &[u8], pos:usize) -> Option<Result<IECGoosePacket,GooseError>>{
+    let (pkt, layout) = match decodeGooseFrame(buffer, pos)?{
+        Ok(frame) => frame,
+        Err(e) => {return Some(Err(e));}
+    };
+    match lintGoosePdu(buffer, layout.appid_pos + 8){
+        Ok(violations) if violations.is_empty() => Some(Ok(pkt)),
+        Ok(violations) => {
+            let count = violations.len();
+            let mut e = GooseError::from(violations.into_iter().next()?);
+            if count > 1{
+                e.message = format!("{} ({} more deviations)", e.message, count - 1);
+            }
+            Some(Err(e))
+        },
+        Err(e) => Some(Err(e)),
+    }
+}
+
+// deviations in the goosePdu starting at pos
+pub fn lintGoosePdu(buffer: &[u8], pos:usize) -> Result<Vec<BerViolation>,GooseError>{
+    let mut linter = Linter{ buffer, violations: vec![] };
+    let (tag, value_pos, end) = linter.tag_length(pos)?;
+    if tag != 0x61{
+        return Err(GooseError{ message: format!("unexpected goosePdu tag 0x{:02x}", tag), pos });
+    }
+
+    let mut new_pos = value_pos;
+    while new_pos < end{
+        let (tag, value_pos, field_end) = linter.tag_length(new_pos)?;
+        linter.check_within(new_pos, field_end, end)?;
+        match tag{
+            // timeAllowedtoLive, stNum, sqNum, confRev, numDatSetEntries
+            0x81 | 0x85 | 0x86 | 0x88 | 0x8a => linter.integer(value_pos, field_end, false),
+            // simulation, ndsCom
+            0x87 | 0x89 => linter.boolean(value_pos, field_end),
+            0xab => linter.data_sequence(value_pos, field_end, 0)?,
+            _ => {},
+        }
+        new_pos = field_end;
+    }
+    Ok(linter.violations)
+}
+
+struct Linter<'a> {
+    buffer: &'a [u8],
+    violations: Vec<BerViolation>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, kind: BerViolationKind, pos: usize, message: String){
+        self.violations.push(BerViolation{ kind, pos, message });
+    }
+
+    // tag, value position and end of the element at pos, checking the length form on the way
+    fn tag_length(&mut self, pos: usize) -> Result<(u8, usize, usize),GooseError>{
+        let mut tag:u8 = 0;
+        let mut length:usize = 0;
+        let value_pos = decode_tag_length(&mut tag, &mut length, self.buffer, pos)?;
+        check_bounds(self.buffer, value_pos, length)?;
+
+        let octets = value_pos - pos - 1;
+        if octets != size_length(length){
+            self.report(BerViolationKind::NonMinimalLength, pos + 1, format!("length {} of tag 0x{:02x} in {} octets", length, tag, octets));
+        }
+        Ok((tag, value_pos, value_pos + length))
+    }
+
+    fn check_within(&self, pos: usize, end: usize, parent_end: usize) -> Result<(),GooseError>{
+        if end > parent_end{
+            return Err(GooseError{ message: "element exceeds its enclosing element".into(), pos });
+        }
+        Ok(())
+    }
+
+    fn integer(&mut self, pos: usize, end: usize, unsigned: bool){
+        let value = &self.buffer[pos..end];
+        if value.len() > 1 && ((value[0] == 0x00 && value[1] & 0x80 == 0) || (value[0] == 0xff && value[1] & 0x80 != 0)){
+            self.report(BerViolationKind::NonMinimalInteger, pos, format!("integer in {} octets with redundant leading 0x{:02x}", value.len(), value[0]));
+        }
+        if unsigned && value.first().is_some_and(|first| first & 0x80 != 0){
+            self.report(BerViolationKind::NegativeUnsigned, pos, format!("unsigned in {} octets with the sign bit set", value.len()));
+        }
+    }
+
+    fn boolean(&mut self, pos: usize, end: usize){
+        if end == pos + 1 && self.buffer[pos] != 0x00 && self.buffer[pos] != 0xff{
+            self.report(BerViolationKind::InvalidBoolean, pos, format!("boolean 0x{:02x}", self.buffer[pos]));
+        }
+    }
+
+    fn float(&mut self, pos: usize, end: usize){
+        let octets = end.saturating_sub(pos + 1);
+        if (octets == 4 || octets == 8) && self.buffer[pos] != float_exponent_width(octets){
+            self.report(BerViolationKind::FloatExponentWidth, pos, format!("float{} exponent width {}", octets * 8, self.buffer[pos]));
+        }
+    }
+
+    fn bit_string(&mut self, pos: usize, end: usize){
+        if end < pos + 2{
+            return;
+        }
+        let padding = self.buffer[pos];
+        let last = self.buffer[end - 1];
+        if padding <= 7 && last & ((1_u16 << padding) - 1) as u8 != 0{
+            self.report(BerViolationKind::BitStringPadding, end - 1, format!("{} unused bits of bit string set", padding));
+        }
+    }
+
+    // allData, array or structure elements between pos and end
+    fn data_sequence(&mut self, pos: usize, end: usize, depth: usize) -> Result<(),GooseError>{
+        if depth > MAX_DATA_DEPTH{
+            return Err(GooseError{ message: format!("data nested deeper than {}", MAX_DATA_DEPTH), pos });
+        }
+        let mut new_pos = pos;
+        while new_pos < end{
+            let (tag, value_pos, element_end) = self.tag_length(new_pos)?;
+            self.check_within(new_pos, element_end, end)?;
+            match tag{
+                0x83 => self.boolean(value_pos, element_end),
+                0x85 => self.integer(value_pos, element_end, false),
+                0x86 => self.integer(value_pos, element_end, true),
+                0x87 => self.float(value_pos, element_end),
+                0x84 => self.bit_string(value_pos, element_end),
+                0xa1 | 0xa2 => self.data_sequence(value_pos, element_end, depth + 1)?,
+                _ => {},
+            }
+            new_pos = element_end;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::{encodeGoosePacket,sizeGoosePacket,decodeGoosePacket};
+
+    // goosePdu holding fields, offsets in the violations count from its tag
+    fn pdu(fields: &[u8]) -> Vec<u8>{
+        let mut buffer = vec![0x61, fields.len() as u8];
+        buffer.extend_from_slice(fields);
+        buffer
+    }
+
+    // goosePdu with allData holding elements
+    fn data(elements: &[u8]) -> Vec<u8>{
+        let mut fields = vec![0xab, elements.len() as u8];
+        fields.extend_from_slice(elements);
+        pdu(&fields)
+    }
+
+    fn lint(buffer: &[u8]) -> Vec<(BerViolationKind, usize)>{
+        lintGoosePdu(buffer, 0).unwrap().into_iter().map(|violation| (violation.kind, violation.pos)).collect()
+    }
+
+    #[test]
+    fn violations_at_their_offset(){
+        assert_eq!(lint(&[0x61, 0x81, 0x03, 0x85, 0x01, 0x05]), vec![(BerViolationKind::NonMinimalLength, 1)]);
+        assert_eq!(lint(&pdu(&[0x80, 0x82, 0x00, 0x01, 0x41])), vec![(BerViolationKind::NonMinimalLength, 3)]);
+        assert_eq!(lint(&pdu(&[0x85, 0x02, 0x00, 0x05])), vec![(BerViolationKind::NonMinimalInteger, 4)]);
+        assert_eq!(lint(&data(&[0x85, 0x02, 0xff, 0x80])), vec![(BerViolationKind::NonMinimalInteger, 6)]);
+        assert_eq!(lint(&pdu(&[0x87, 0x01, 0x01])), vec![(BerViolationKind::InvalidBoolean, 4)]);
+        assert_eq!(lint(&data(&[0x83, 0x01, 0x01])), vec![(BerViolationKind::InvalidBoolean, 6)]);
+        assert_eq!(lint(&data(&[0x87, 0x05, 0x0b, 0x42, 0x48, 0x00, 0x00])), vec![(BerViolationKind::FloatExponentWidth, 6)]);
+        assert_eq!(lint(&data(&[0x87, 0x09, 0x08, 0x40, 0x49, 0, 0, 0, 0, 0, 0])), vec![(BerViolationKind::FloatExponentWidth, 6)]);
+        assert_eq!(lint(&data(&[0x84, 0x03, 0x03, 0x00, 0x41])), vec![(BerViolationKind::BitStringPadding, 8)]);
+        assert_eq!(lint(&data(&[0x86, 0x01, 0xff])), vec![(BerViolationKind::NegativeUnsigned, 6)]);
+        // nested in a structure
+        assert_eq!(lint(&data(&[0xa2, 0x06, 0x83, 0x01, 0xff, 0x83, 0x01, 0x02])), vec![(BerViolationKind::InvalidBoolean, 11)]);
+    }
+
+    #[test]
+    fn canonical_elements_pass(){
+        let elements = [
+            0x83, 0x01, 0xff, 0x83, 0x01, 0x00,
+            0x85, 0x01, 0x80, 0x85, 0x02, 0x00, 0x80, 0x86, 0x02, 0x00, 0xff,
+            0x87, 0x05, 0x08, 0x42, 0x48, 0x00, 0x00,
+            0x84, 0x03, 0x03, 0x00, 0x40,
+        ];
+        assert!(lint(&data(&elements)).is_empty());
+        // the goosePdu counters are INTEGER, a set sign bit is not reported there
+        assert!(lint(&pdu(&[0x85, 0x01, 0xff])).is_empty());
+    }
+
+    fn packet() -> IECGoosePacket{
+        let mut pkt = IECGoosePacket::default();
+        pkt.pdu.allData = vec![
+            IECData::boolean(true),
+            IECData::int32(0x80),
+            IECData::float64(50.0),
+            IECData::bit_string{ padding: 3, val: vec![0x00, 0x40] },
+        ];
+        pkt.pdu.numDatSetEntries = 4;
+        pkt
+    }
+
+    fn encode(pkt: &IECGoosePacket) -> Vec<u8>{
+        let mut buffer = vec![0_u8;sizeGoosePacket(pkt)];
+        encodeGoosePacket(pkt, &mut buffer, 0).unwrap();
+        buffer
+    }
+
+    fn find(frame: &[u8], pattern: &[u8]) -> usize{
+        frame.windows(pattern.len()).position(|window| window == pattern).unwrap()
+    }
+
+    #[test]
+    fn strict_decoding(){
+        let frame = encode(&packet());
+        assert!(lintGoosePacket(&frame, 0).unwrap().unwrap().is_empty());
+        assert_eq!(decodeGoosePacketStrict(&frame, 0).unwrap().unwrap().pdu.allData.len(), 4);
+
+        // same length patches of the canonical frame, each one deviation at a known offset
+        let boolean = find(&frame, &[0x83, 0x01, 0xff]) + 2;
+        let integer = find(&frame, &[0x85, 0x02, 0x00, 0x80]) + 2;
+        let float = find(&frame, &[0x87, 0x09, 0x0b]) + 2;
+        // octets go out reversed, the last one holds the padding
+        let bit_string = find(&frame, &[0x84, 0x03, 0x03, 0x02, 0x00]) + 4;
+        let patches = [
+            (boolean, 0x01, BerViolationKind::InvalidBoolean),
+            (integer + 1, 0x05, BerViolationKind::NonMinimalInteger),
+            (float, 0x08, BerViolationKind::FloatExponentWidth),
+            (bit_string, 0x01, BerViolationKind::BitStringPadding),
+        ];
+        for (pos, value, kind) in patches{
+            let mut deviant = frame.clone();
+            deviant[pos] = value;
+            let violations = lintGoosePacket(&deviant, 0).unwrap().unwrap();
+            let expected_pos = if kind == BerViolationKind::NonMinimalInteger {integer} else {pos};
+            assert_eq!(violations.iter().map(|violation| (violation.kind, violation.pos)).collect::<Vec<_>>(), vec![(kind, expected_pos)]);
+
+            assert!(decodeGoosePacket(&deviant, 0).unwrap().is_ok());
+            let e = decodeGoosePacketStrict(&deviant, 0).unwrap().unwrap_err();
+            assert_eq!(e.pos, expected_pos);
+        }
+    }
+}
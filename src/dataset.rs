@@ -0,0 +1,156 @@
+#![allow(non_snake_case)]
+
+// names for the anonymous allData entries: FCDA references, LD0/XCBR1.Pos.stVal[ST], bound to
+// the position of the value in allData, nested attributes to the path through the structures
+
+use std::collections::HashMap;
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::schema::{IECDataSchema,DataSetSchema};
+
+#[derive(Debug,Default,Clone)]
+pub struct DataSetBinding {
+    paths: HashMap<String, Vec<usize>>,
+    // reference without the logical device, None when it is not unique
+    short: HashMap<String, Option<String>>,
+    // declared types, e.g. from SCL, unknown for a binding over decoded data
+    schema: Option<DataSetSchema>,
+}
+
+fn shortReference(reference: &str) -> Option<&str>{
+    reference.split_once('/').map(|(_, short)| short)
+}
+
+impl DataSetBinding {
+    // one reference per allData entry, in dataset order
+    pub fn new<S: AsRef<str>>(fcdas: &[S]) -> DataSetBinding{
+        let mut binding = DataSetBinding::default();
+        for (index, fcda) in fcdas.iter().enumerate(){
+            binding.bind(fcda.as_ref(), vec![index]);
+        }
+        binding
+    }
+
+    // path holds the allData index followed by the structure or array indices
+    pub fn bind(&mut self, reference: &str, path: Vec<usize>){
+        if let Some(short) = shortReference(reference){
+            self.short.entry(short.to_string())
+                .and_modify(|full| if full.as_deref() != Some(reference) {*full = None})
+                .or_insert_with(|| Some(reference.to_string()));
+        }
+        self.paths.insert(reference.to_string(), path);
+    }
+
+    // values set later keep the declared types instead of only their signedness
+    pub fn declare(&mut self, schema: DataSetSchema){
+        self.schema = Some(schema);
+    }
+
+    pub fn references(&self) -> impl Iterator<Item = &str>{
+        self.paths.keys().map(String::as_str)
+    }
+
+    // the logical device can be left out when the rest of the reference is unique
+    pub fn path(&self, reference: &str) -> Option<&[usize]>{
+        if let Some(path) = self.paths.get(reference){
+            return Some(path);
+        }
+        let full = self.short.get(reference)?.as_ref()?;
+        self.paths.get(full).map(Vec::as_slice)
+    }
+
+    pub fn get<'a>(&self, allData: &'a [IECData], reference: &str) -> Option<&'a IECData>{
+        let (index, nested) = self.path(reference)?.split_first()?;
+        let mut data = allData.get(*index)?;
+        for index in nested{
+            data = match data{
+                IECData::array(val) | IECData::structure(val) => val.get(*index)?,
+                _ => return None,
+            };
+        }
+        Some(data)
+    }
+
+    pub fn get_mut<'a>(&self, allData: &'a mut [IECData], reference: &str) -> Option<&'a mut IECData>{
+        let (index, nested) = self.path(reference)?.split_first()?;
+        let mut data = allData.get_mut(*index)?;
+        for index in nested{
+            data = match data{
+                IECData::array(val) | IECData::structure(val) => val.get_mut(*index)?,
+                _ => return None,
+            };
+        }
+        Some(data)
+    }
+
+    // replaces the value, which has to keep its type so the dataset layout does not change.
+    // integers have to fit the declared type and are stored in it. without declared types
+    // integers only have to keep their signedness, a decoded int32 of 1 reads back as int8
+    pub fn set<V: Into<IECData>>(&self, allData: &mut [IECData], reference: &str, value: V) -> Result<(),GooseError>{
+        let declared = self.schema.as_ref().zip(self.path(reference)).and_then(|(schema, path)| schema.member(path)).cloned();
+        let data = self.get_mut(allData, reference).ok_or_else(|| GooseError{ message: format!("{} not found in the dataset", reference), pos: 0 })?;
+        let value = value.into();
+        match declared{
+            Some(schema) => {
+                schema.validate(&value, reference)?;
+                *data = schema.cast(value);
+            },
+            None => {
+                IECDataSchema::from_data(data).widened().validate(&value, reference)?;
+                *data = value;
+            },
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu_encoder::encodeIECDataElement;
+    use crate::pdu_decoder::decodeIECDataElement;
+
+    #[test]
+    fn set_decoded_integers(){
+        let mut buffer = [0_u8;32];
+        let end = encodeIECDataElement(&IECData::structure(vec![IECData::int32(1), IECData::int32u(2)]), &mut buffer, 0);
+        let (_, decoded) = decodeIECDataElement(&buffer[..end], 0).unwrap();
+        let mut allData = vec![decoded];
+
+        let mut binding = DataSetBinding::default();
+        binding.bind("LD0/MMXU1.TotW.mag.i[MX]", vec![0, 0]);
+        binding.bind("LD0/MMXU1.TotW.q[MX]", vec![0, 1]);
+
+        binding.set(&mut allData, "LD0/MMXU1.TotW.mag.i[MX]", IECData::int32(300)).unwrap();
+        assert!(matches!(binding.get(&allData, "MMXU1.TotW.mag.i[MX]"), Some(IECData::int32(300))));
+        binding.set(&mut allData, "LD0/MMXU1.TotW.q[MX]", IECData::int64u(70000)).unwrap();
+
+        let e = binding.set(&mut allData, "LD0/MMXU1.TotW.mag.i[MX]", IECData::int32u(300)).unwrap_err();
+        assert_eq!(e.message, "LD0/MMXU1.TotW.mag.i[MX]: expected int64, got int32u");
+        assert!(binding.set(&mut allData, "LD0/MMXU1.TotW.q[MX]", IECData::boolean(true)).is_err());
+    }
+
+    #[test]
+    fn set_declared_integers(){
+        let schema = DataSetSchema::new(vec![IECDataSchema::structure(vec![IECDataSchema::int32, IECDataSchema::int8u])]);
+        let mut allData = schema.default_data();
+        let mut binding = DataSetBinding::default();
+        binding.bind("LD0/MMXU1.TotW.mag.i[MX]", vec![0, 0]);
+        binding.bind("LD0/MMXU1.TotW.q[MX]", vec![0, 1]);
+        binding.declare(schema);
+
+        // stored in the declared type whatever width the value came in
+        binding.set(&mut allData, "MMXU1.TotW.mag.i[MX]", IECData::int64(-300)).unwrap();
+        assert!(matches!(binding.get(&allData, "MMXU1.TotW.mag.i[MX]"), Some(IECData::int32(-300))));
+        binding.set(&mut allData, "MMXU1.TotW.q[MX]", IECData::int32u(200)).unwrap();
+        assert!(matches!(binding.get(&allData, "MMXU1.TotW.q[MX]"), Some(IECData::int8u(200))));
+
+        let e = binding.set(&mut allData, "MMXU1.TotW.mag.i[MX]", IECData::int64(1 << 40)).unwrap_err();
+        assert_eq!(e.message, "MMXU1.TotW.mag.i[MX]: 1099511627776 does not fit int32");
+        let e = binding.set(&mut allData, "MMXU1.TotW.mag.i[MX]", IECData::int32u(300)).unwrap_err();
+        assert_eq!(e.message, "MMXU1.TotW.mag.i[MX]: expected int32, got int32u");
+        assert!(binding.set(&mut allData, "MMXU1.TotW.q[MX]", IECData::int16u(256)).is_err());
+        assert!(matches!(binding.get(&allData, "MMXU1.TotW.mag.i[MX]"), Some(IECData::int32(-300))));
+    }
+}
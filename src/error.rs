@@ -21,3 +21,33 @@ impl std::error::Error for GooseError {
         &self.message
     }
 }
+
+// decoding a GOOSE frame that has to carry a valid IEC 62351-6 authentication value
+#[derive(Debug, Clone)]
+pub enum GooseSecurityError {
+    Malformed(GooseError),
+    // the key provider has no key for this stream
+    UnknownKey,
+    // reserved 1 announces no authentication extension
+    MissingAuthentication,
+    AuthenticationFailed,
+}
+
+impl From<GooseError> for GooseSecurityError {
+    fn from(e: GooseError) -> GooseSecurityError {
+        GooseSecurityError::Malformed(e)
+    }
+}
+
+impl fmt::Display for GooseSecurityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            GooseSecurityError::Malformed(e) => write!(f, "malformed frame: {}", e),
+            GooseSecurityError::UnknownKey => write!(f, "no key for this GOOSE stream"),
+            GooseSecurityError::MissingAuthentication => write!(f, "GOOSE frame is not authenticated"),
+            GooseSecurityError::AuthenticationFailed => write!(f, "GOOSE authentication failed"),
+        }
+    }
+}
+
+impl std::error::Error for GooseSecurityError {}
@@ -0,0 +1,192 @@
+#![allow(non_snake_case)]
+
+// pre-encoded GOOSE frame for retransmissions: stNum and sqNum are reserved at their widest
+// encoding (0x00 plus 4 octets) so the lengths never shift, then stNum, sqNum and t are
+// patched in place instead of encoding the whole pdu again. the PRP and HSR sequence numbers
+// advance with every update, the duplicate discard of the receiving nodes would drop the
+// retransmissions otherwise
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::pdu::{encodeGooseFrame,sizeFrame,sizeEthernetHeader,simulationHeader,GOOSE_ETHER_TYPE};
+use crate::stream_encoder::{GooseSizeTree,fillIECGoosePduToSlice};
+use crate::basic_decoder::decode_tag_length;
+use crate::security::{GooseKey,sizeAuthenticationExtension,encodeAuthenticationExtension};
+
+const COUNTER_SIZE:usize = 5;
+const TIME_SIZE:usize = 8;
+
+// the reserved counters are not minimal BER, lintGoosePacket reports a NonMinimalInteger for
+// stNum and sqNum whenever their value fits fewer octets and decodeGoosePacketStrict rejects the
+// frame. conformance test cases need frames from encodeGoosePacket, a template trades the
+// canonical encoding for lengths that never shift
+#[derive(Debug,Clone)]
+pub struct GooseFrameTemplate {
+    buffer: Vec<u8>,
+    // value offsets of t, stNum and sqNum in buffer
+    t_pos: usize,
+    stNum_pos: usize,
+    sqNum_pos: usize,
+    // span covered by the authentication value
+    appid_pos: usize,
+    pdu_end: usize,
+    key: Option<GooseKey>,
+    // offsets of the PRP trailer and HSR tag sequence numbers
+    prp_pos: Option<usize>,
+    hsr_pos: Option<usize>,
+}
+
+impl GooseFrameTemplate {
+    pub fn new(pkt: &IECGoosePacket) -> Result<GooseFrameTemplate,GooseError>{
+        GooseFrameTemplate::encode(pkt, None)
+    }
+
+    // the authentication value is recomputed after every update
+    pub fn new_secure(pkt: &IECGoosePacket, key: GooseKey) -> Result<GooseFrameTemplate,GooseError>{
+        GooseFrameTemplate::encode(pkt, Some(key))
+    }
+
+    fn encode(pkt: &IECGoosePacket, key: Option<GooseKey>) -> Result<GooseFrameTemplate,GooseError>{
+        let goose_hdr = simulationHeader(pkt)?;
+
+        // u32::MAX takes the widest encoding, the real values are patched in below
+        let mut pdu = pkt.pdu.clone();
+        pdu.stNum = u32::MAX;
+        pdu.sqNum = u32::MAX;
+
+        let extension_size = key.as_ref().map_or(0, sizeAuthenticationExtension);
+        let tree = GooseSizeTree::new(&pdu);
+        let mut buffer = vec![0_u8;sizeFrame(&pkt.eth_hdr, &pkt.prp, tree.frame_size(), extension_size)];
+        let size = encodeGooseFrame(&pkt.eth_hdr, &goose_hdr, GOOSE_ETHER_TYPE, &pkt.prp, key.as_ref(), &mut buffer, 0, |buffer, pos| fillIECGoosePduToSlice(&pdu, &tree, buffer, pos))?;
+        buffer.truncate(size);
+
+        let appid_pos = sizeEthernetHeader(&pkt.eth_hdr) + 2;
+        let pdu_pos = appid_pos + IECGooseHeader::getSize() - 2;
+
+        let mut tag:u8 = 0;
+        let mut length:usize = 0;
+        let mut new_pos = decode_tag_length(&mut tag, &mut length, &buffer, pdu_pos)?;
+        let pdu_end = new_pos + length;
+
+        let mut t_pos = None;
+        let mut stNum_pos = None;
+        let mut sqNum_pos = None;
+        while new_pos < pdu_end && sqNum_pos.is_none(){
+            new_pos = decode_tag_length(&mut tag, &mut length, &buffer, new_pos)?;
+            match (tag, length){
+                (0x84, TIME_SIZE) => t_pos = Some(new_pos),
+                (0x85, COUNTER_SIZE) => stNum_pos = Some(new_pos),
+                (0x86, COUNTER_SIZE) => sqNum_pos = Some(new_pos),
+                _ => {},
+            }
+            new_pos += length;
+        }
+
+        match (t_pos, stNum_pos, sqNum_pos){
+            (Some(t_pos), Some(stNum_pos), Some(sqNum_pos)) => {
+                let prp_pos = pkt.prp.as_ref().map(|_| buffer.len() - IECPRP1::getSize());
+                // sequence number, then the EtherType in front of APPID
+                let hsr_pos = pkt.eth_hdr.HSR.as_ref().map(|_| appid_pos - 4);
+                let mut template = GooseFrameTemplate{ buffer, t_pos, stNum_pos, sqNum_pos, appid_pos, pdu_end, key, prp_pos, hsr_pos };
+                template.patch(pkt.pdu.stNum, pkt.pdu.sqNum, &pkt.pdu.t);
+                Ok(template)
+            },
+            _ => Err(GooseError{ message: "t, stNum or sqNum not found in the encoded goosePdu".into(), pos: pdu_pos }),
+        }
+    }
+
+    // the frame as it goes on the wire
+    pub fn frame(&self) -> &[u8]{
+        &self.buffer
+    }
+
+    // the next frame, with the PRP and HSR sequence numbers one past the previous frame
+    pub fn update(&mut self, stNum: u32, sqNum: u32, t: &UtcTime) -> &[u8]{
+        for pos in [self.prp_pos, self.hsr_pos].into_iter().flatten(){
+            let sequence = self.sequence(pos).wrapping_add(1);
+            self.buffer[pos..pos+2].copy_from_slice(&sequence.to_be_bytes());
+        }
+        self.patch(stNum, sqNum, t)
+    }
+
+    // hands the sequence numbers of the next frame to pkt, a template built from it for changed
+    // data then carries on where this one stopped
+    pub fn continue_sequences(&self, pkt: &mut IECGoosePacket){
+        if let (Some(prp), Some(pos)) = (pkt.prp.as_mut(), self.prp_pos){
+            prp.sequence = self.sequence(pos).wrapping_add(1);
+        }
+        if let (Some(hsr), Some(pos)) = (pkt.eth_hdr.HSR.as_mut(), self.hsr_pos){
+            hsr.sequence = self.sequence(pos).wrapping_add(1);
+        }
+    }
+
+    fn sequence(&self, pos: usize) -> u16{
+        u16::from_be_bytes([self.buffer[pos], self.buffer[pos+1]])
+    }
+
+    fn patch(&mut self, stNum: u32, sqNum: u32, t: &UtcTime) -> &[u8]{
+        self.buffer[self.stNum_pos+1..self.stNum_pos+COUNTER_SIZE].copy_from_slice(&stNum.to_be_bytes());
+        self.buffer[self.sqNum_pos+1..self.sqNum_pos+COUNTER_SIZE].copy_from_slice(&sqNum.to_be_bytes());
+        self.buffer[self.t_pos..self.t_pos+TIME_SIZE].copy_from_slice(&t.to_bytes());
+
+        if let Some(key) = &self.key{
+            encodeAuthenticationExtension(key, &mut self.buffer, self.appid_pos, self.pdu_end);
+        }
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::decodeGoosePacket;
+    use crate::conformance::{BerViolationKind,lintGoosePacket};
+
+    #[test]
+    fn update_advances_redundancy_sequences(){
+        let mut pkt = IECGoosePacket{
+            prp: Some(IECPRP1{ sequence: 7, lan: IECPRPLAN::LAN_A, frame_size: 0 }),
+            ..Default::default()
+        };
+        pkt.eth_hdr.HSR = Some(IECHSR{ path: 0, frame_size: 0, sequence: 0xfffe });
+        pkt.pdu.allData = vec![IECData::boolean(true)];
+        pkt.pdu.numDatSetEntries = 1;
+
+        let mut template = GooseFrameTemplate::new(&pkt).unwrap();
+        let mut sequences = vec![];
+        for sqNum in 0..3{
+            let frame = if sqNum == 0 {template.frame()} else {template.update(1, sqNum, &pkt.pdu.t)};
+            let decoded = decodeGoosePacket(frame, 0).unwrap().unwrap();
+            assert_eq!(decoded.pdu.sqNum, sqNum);
+            sequences.push((decoded.prp.unwrap().sequence, decoded.eth_hdr.HSR.unwrap().sequence));
+        }
+        assert_eq!(sequences, vec![(7, 0xfffe), (8, 0xffff), (9, 0)]);
+
+        template.continue_sequences(&mut pkt);
+        assert_eq!(pkt.prp.unwrap().sequence, 10);
+        assert_eq!(pkt.eth_hdr.HSR.unwrap().sequence, 1);
+    }
+
+    #[test]
+    fn counters_keep_their_width(){
+        let mut pkt = IECGoosePacket::default();
+        pkt.pdu.stNum = 1;
+        let mut template = GooseFrameTemplate::new(&pkt).unwrap();
+        let size = template.frame().len();
+
+        for (stNum, sqNum) in [(1, 127), (1, 128), (u32::MAX, u32::MAX)]{
+            let frame = template.update(stNum, sqNum, &pkt.pdu.t);
+            assert_eq!(frame.len(), size);
+            let decoded = decodeGoosePacket(frame, 0).unwrap().unwrap();
+            assert_eq!((decoded.pdu.stNum, decoded.pdu.sqNum), (stNum, sqNum));
+        }
+
+        // only the counters deviate from the canonical encoding, and only while they fit fewer octets
+        let frame = template.update(1, 2, &pkt.pdu.t).to_vec();
+        let violations = lintGoosePacket(&frame, 0).unwrap().unwrap();
+        let positions: Vec<usize> = violations.iter().map(|violation| violation.pos).collect();
+        assert!(violations.iter().all(|violation| violation.kind == BerViolationKind::NonMinimalInteger));
+        assert_eq!(positions, vec![template.stNum_pos, template.sqNum_pos]);
+        assert!(lintGoosePacket(template.update(u32::MAX, u32::MAX, &pkt.pdu.t), 0).unwrap().unwrap().is_empty());
+    }
+}
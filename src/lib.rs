@@ -4,4 +4,21 @@ pub mod basic_encoder;
 pub mod basic_decoder;
 pub mod pdu_encoder;
 pub mod pdu_decoder;
-pub mod error;
\ No newline at end of file
+pub mod pdu_ref;
+pub mod sv_encoder;
+pub mod sv_decoder;
+pub mod error;
+pub mod utc_time;
+pub mod publisher;
+pub mod subscriber;
+pub mod pcap;
+pub mod rgoose;
+pub mod security;
+pub mod redundancy;
+pub mod stream_encoder;
+pub mod frame_template;
+pub mod scl;
+pub mod schema;
+pub mod attributes;
+pub mod dataset;
+pub mod conformance;
\ No newline at end of file
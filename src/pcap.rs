@@ -0,0 +1,418 @@
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::pdu::{decodeGoosePacket,encodeGoosePacket,sizeGoosePacket};
+use crate::publisher::GooseSink;
+
+pub const LINKTYPE_ETHERNET:u16 = 1;
+
+const PCAP_MAGIC_MICROS:u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS:u32 = 0xa1b2_3c4d;
+const PCAPNG_SECTION_HEADER:u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC:u32 = 0x1a2b_3c4d;
+const PCAPNG_INTERFACE_DESCRIPTION:u32 = 1;
+const PCAPNG_SIMPLE_PACKET:u32 = 3;
+const PCAPNG_ENHANCED_PACKET:u32 = 6;
+// larger records are treated as corrupt instead of allocated
+const MAX_RECORD_SIZE:usize = 1 << 24;
+
+// one captured frame, timestamp is the capture time (UNIX_EPOCH for pcapng simple packet blocks)
+#[derive(Debug,Clone)]
+pub struct PcapRecord {
+    pub timestamp: SystemTime,
+    pub linktype: u16,
+    pub original_length: u32,
+    pub data: Vec<u8>,
+}
+
+// timestamp resolution of a capture interface
+#[derive(Debug,Clone,Copy)]
+enum TsResolution {
+    Decimal(u32),
+    Binary(u32),
+}
+
+impl TsResolution {
+    fn to_duration(self, ticks: u64) -> Duration{
+        match self{
+            TsResolution::Decimal(exponent) => {
+                let per_second = 10_u64.saturating_pow(exponent);
+                let nanos = (ticks % per_second) as u128 * 1_000_000_000 / per_second as u128;
+                Duration::new(ticks / per_second, nanos as u32)
+            },
+            TsResolution::Binary(exponent) => {
+                let nanos = ((ticks as u128) * 1_000_000_000) >> exponent.min(127);
+                Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+            },
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+enum PcapFormat {
+    Pcap{ linktype: u16, resolution: TsResolution },
+    PcapNg{ interfaces: Vec<(u16, TsResolution)> },
+}
+
+// reads classic pcap (micro and nanosecond, either byte order) and pcapng files
+pub struct PcapReader<R: Read> {
+    reader: R,
+    format: PcapFormat,
+    big_endian: bool,
+    offset: usize,
+}
+
+impl<R: Read> PcapReader<R> {
+    pub fn new(mut reader: R) -> Result<PcapReader<R>,GooseError>{
+        let mut magic = [0_u8;4];
+        read_exact(&mut reader, &mut magic, 0)?;
+        let mut pcap = PcapReader{ reader, format: PcapFormat::PcapNg{ interfaces: vec![] }, big_endian: false, offset: 4 };
+
+        let magic_le = u32::from_le_bytes(magic);
+        let magic_be = u32::from_be_bytes(magic);
+        if magic_le == PCAPNG_SECTION_HEADER{
+            pcap.read_section_header()?;
+            return Ok(pcap);
+        }
+        let nanos = if magic_le == PCAP_MAGIC_MICROS || magic_le == PCAP_MAGIC_NANOS{
+            magic_le == PCAP_MAGIC_NANOS
+        }
+        else if magic_be == PCAP_MAGIC_MICROS || magic_be == PCAP_MAGIC_NANOS{
+            pcap.big_endian = true;
+            magic_be == PCAP_MAGIC_NANOS
+        }
+        else{
+            return Err(GooseError{ message: format!("not a pcap or pcapng file (magic {:08x})", magic_be), pos: 0});
+        };
+
+        // version, thiszone, sigfigs, snaplen, network
+        let mut header = [0_u8;20];
+        pcap.read(&mut header)?;
+        let linktype = pcap.u32_at(&header, 16) as u16;
+        let resolution = if nanos { TsResolution::Decimal(9) } else { TsResolution::Decimal(6) };
+        pcap.format = PcapFormat::Pcap{ linktype, resolution };
+        Ok(pcap)
+    }
+
+    // decoded GOOSE frames with their capture time, other EtherTypes and link types are skipped
+    pub fn goose_packets(self) -> GoosePackets<R>{
+        GoosePackets{ reader: self }
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<PcapRecord>,GooseError>{
+        match self.format{
+            PcapFormat::Pcap{ linktype, resolution } => self.next_pcap_record(linktype, resolution),
+            PcapFormat::PcapNg{ .. } => self.next_pcapng_record(),
+        }
+    }
+
+    fn next_pcap_record(&mut self, linktype: u16, resolution: TsResolution) -> Result<Option<PcapRecord>,GooseError>{
+        let mut header = [0_u8;16];
+        if !self.read_or_eof(&mut header)?{
+            return Ok(None);
+        }
+        let seconds = self.u32_at(&header, 0) as u64;
+        let subsec = self.u32_at(&header, 4) as u64;
+        let captured = self.u32_at(&header, 8) as usize;
+        let original_length = self.u32_at(&header, 12);
+        if captured > MAX_RECORD_SIZE{
+            return Err(GooseError{ message: format!("invalid pcap record length {}", captured), pos: self.offset - 8});
+        }
+
+        let timestamp = capture_time(Duration::from_secs(seconds) + resolution.to_duration(subsec), self.offset - 16)?;
+        let mut data = vec![0_u8;captured];
+        self.read(&mut data)?;
+        Ok(Some(PcapRecord{ timestamp, linktype, original_length, data }))
+    }
+
+    fn next_pcapng_record(&mut self) -> Result<Option<PcapRecord>,GooseError>{
+        loop{
+            let block_pos = self.offset;
+            let mut header = [0_u8;8];
+            if !self.read_or_eof(&mut header)?{
+                return Ok(None);
+            }
+            let block_type = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+            if block_type == PCAPNG_SECTION_HEADER{
+                // a new section may switch byte order, its length is read after the byte order magic
+                self.read_section_body(u32::from_le_bytes([header[4], header[5], header[6], header[7]]), block_pos)?;
+                continue;
+            }
+            let block_type = self.u32_at(&header, 0);
+            let block_length = self.u32_at(&header, 4) as usize;
+            if block_length < 12 || !block_length.is_multiple_of(4) || block_length > MAX_RECORD_SIZE{
+                return Err(GooseError{ message: format!("invalid pcapng block length {}", block_length), pos: block_pos});
+            }
+            let mut body = vec![0_u8;block_length - 8];
+            self.read(&mut body)?;
+            let body = &body[..body.len() - 4];
+
+            match block_type{
+                PCAPNG_INTERFACE_DESCRIPTION => {
+                    if body.len() < 8{
+                        return Err(GooseError{ message: "pcapng interface block too short".into(), pos: block_pos});
+                    }
+                    let linktype = self.u16_at(body, 0);
+                    let resolution = self.interface_resolution(&body[8..]);
+                    if let PcapFormat::PcapNg{ interfaces } = &mut self.format{
+                        interfaces.push((linktype, resolution));
+                    }
+                },
+                PCAPNG_ENHANCED_PACKET => {
+                    if body.len() < 20{
+                        return Err(GooseError{ message: "pcapng packet block too short".into(), pos: block_pos});
+                    }
+                    let interface = self.u32_at(body, 0) as usize;
+                    let ticks = (self.u32_at(body, 4) as u64) << 32 | self.u32_at(body, 8) as u64;
+                    let captured = self.u32_at(body, 12) as usize;
+                    let original_length = self.u32_at(body, 16);
+                    if 20 + captured > body.len(){
+                        return Err(GooseError{ message: "pcapng packet data exceeds block".into(), pos: block_pos});
+                    }
+                    let (linktype, resolution) = self.interface(interface, block_pos)?;
+                    let timestamp = capture_time(resolution.to_duration(ticks), block_pos)?;
+                    return Ok(Some(PcapRecord{ timestamp, linktype, original_length, data: body[20..20+captured].to_vec() }));
+                },
+                PCAPNG_SIMPLE_PACKET => {
+                    if body.len() < 4{
+                        return Err(GooseError{ message: "pcapng simple packet block too short".into(), pos: block_pos});
+                    }
+                    let original_length = self.u32_at(body, 0);
+                    let captured = (original_length as usize).min(body.len() - 4);
+                    let (linktype, _) = self.interface(0, block_pos)?;
+                    return Ok(Some(PcapRecord{ timestamp: UNIX_EPOCH, linktype, original_length, data: body[4..4+captured].to_vec() }));
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn interface(&self, index: usize, pos: usize) -> Result<(u16, TsResolution),GooseError>{
+        match &self.format{
+            PcapFormat::PcapNg{ interfaces } if index < interfaces.len() => Ok(interfaces[index]),
+            _ => Err(GooseError{ message: format!("packet for undefined pcapng interface {}", index), pos}),
+        }
+    }
+
+    // if_tsresol option, microseconds when absent
+    fn interface_resolution(&self, mut options: &[u8]) -> TsResolution{
+        while options.len() >= 4{
+            let code = self.u16_at(options, 0);
+            let length = self.u16_at(options, 2) as usize;
+            let padded = (length + 3) & !3;
+            if code == 0 || 4 + length > options.len(){
+                break;
+            }
+            if code == 9 && length == 1{
+                let value = options[4];
+                return if value & 0x80 == 0 { TsResolution::Decimal(value as u32) } else { TsResolution::Binary((value & 0x7f) as u32) };
+            }
+            options = &options[(4 + padded).min(options.len())..];
+        }
+        TsResolution::Decimal(6)
+    }
+
+    fn read_section_header(&mut self) -> Result<(),GooseError>{
+        let mut length = [0_u8;4];
+        self.read(&mut length)?;
+        self.read_section_body(u32::from_le_bytes(length), 0)
+    }
+
+    // byte order magic decides how the already read block length is interpreted
+    fn read_section_body(&mut self, raw_length: u32, block_pos: usize) -> Result<(),GooseError>{
+        let mut magic = [0_u8;4];
+        self.read(&mut magic)?;
+        self.big_endian = match u32::from_le_bytes(magic){
+            PCAPNG_BYTE_ORDER_MAGIC => false,
+            _ if u32::from_be_bytes(magic) == PCAPNG_BYTE_ORDER_MAGIC => true,
+            _ => return Err(GooseError{ message: "invalid pcapng byte order magic".into(), pos: block_pos + 8}),
+        };
+        let block_length = if self.big_endian { raw_length.swap_bytes() } else { raw_length } as usize;
+        if block_length < 28 || !block_length.is_multiple_of(4) || block_length > MAX_RECORD_SIZE{
+            return Err(GooseError{ message: format!("invalid pcapng section length {}", block_length), pos: block_pos});
+        }
+        let mut rest = vec![0_u8;block_length - 12];
+        self.read(&mut rest)?;
+        self.format = PcapFormat::PcapNg{ interfaces: vec![] };
+        Ok(())
+    }
+
+    fn u16_at(&self, bytes: &[u8], at: usize) -> u16{
+        let raw = [bytes[at], bytes[at+1]];
+        if self.big_endian { u16::from_be_bytes(raw) } else { u16::from_le_bytes(raw) }
+    }
+
+    fn u32_at(&self, bytes: &[u8], at: usize) -> u32{
+        let raw = [bytes[at], bytes[at+1], bytes[at+2], bytes[at+3]];
+        if self.big_endian { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) }
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(),GooseError>{
+        read_exact(&mut self.reader, buffer, self.offset)?;
+        self.offset += buffer.len();
+        Ok(())
+    }
+
+    // false on a clean end of file before the first byte
+    fn read_or_eof(&mut self, buffer: &mut [u8]) -> Result<bool,GooseError>{
+        let mut filled = 0;
+        while filled < buffer.len(){
+            match self.reader.read(&mut buffer[filled..]){
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(GooseError{ message: "truncated capture file".into(), pos: self.offset + filled}),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(GooseError{ message: e.to_string(), pos: self.offset + filled}),
+            }
+        }
+        self.offset += filled;
+        Ok(true)
+    }
+}
+
+// huge tick counts at a coarse if_tsresol run past what SystemTime can hold
+fn capture_time(since_the_epoch: Duration, pos: usize) -> Result<SystemTime,GooseError>{
+    UNIX_EPOCH.checked_add(since_the_epoch).ok_or_else(|| GooseError{ message: format!("capture time {} s after the epoch out of range", since_the_epoch.as_secs()), pos})
+}
+
+fn read_exact<R: Read>(reader: &mut R, buffer: &mut [u8], pos: usize) -> Result<(),GooseError>{
+    reader.read_exact(buffer).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof{
+            GooseError{ message: "truncated capture file".into(), pos}
+        }
+        else{
+            GooseError{ message: e.to_string(), pos}
+        }
+    })
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<PcapRecord,GooseError>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        self.next_record().transpose()
+    }
+}
+
+pub struct GoosePackets<R: Read> {
+    reader: PcapReader<R>,
+}
+
+impl<R: Read> Iterator for GoosePackets<R> {
+    type Item = Result<(SystemTime, IECGoosePacket),GooseError>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        loop{
+            let record = match self.reader.next_record(){
+                Ok(Some(record)) => record,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            if record.linktype != LINKTYPE_ETHERNET{
+                continue;
+            }
+            match decodeGoosePacket(&record.data, 0){
+                Some(Ok(pkt)) => return Some(Ok((record.timestamp, pkt))),
+                Some(Err(e)) => return Some(Err(e)),
+                None => {},
+            }
+        }
+    }
+}
+
+// writes classic pcap with nanosecond timestamps and Ethernet link type
+pub struct PcapWriter<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> PcapWriter<W> {
+    pub fn new(mut writer: W) -> Result<PcapWriter<W>,GooseError>{
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&PCAP_MAGIC_NANOS.to_le_bytes());
+        header.extend_from_slice(&2_u16.to_le_bytes());
+        header.extend_from_slice(&4_u16.to_le_bytes());
+        header.extend_from_slice(&0_i32.to_le_bytes());
+        header.extend_from_slice(&0_u32.to_le_bytes());
+        header.extend_from_slice(&65535_u32.to_le_bytes());
+        header.extend_from_slice(&(LINKTYPE_ETHERNET as u32).to_le_bytes());
+        writer.write_all(&header).map_err(write_error)?;
+        Ok(PcapWriter{ writer, buffer: vec![] })
+    }
+
+    pub fn write_frame(&mut self, timestamp: SystemTime, frame: &[u8]) -> Result<(),GooseError>{
+        let since_the_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+        let seconds = u32::try_from(since_the_epoch.as_secs()).map_err(|_| GooseError{ message: "timestamp does not fit pcap".into(), pos: 0})?;
+        let mut header = [0_u8;16];
+        header[0..4].copy_from_slice(&seconds.to_le_bytes());
+        header[4..8].copy_from_slice(&since_the_epoch.subsec_nanos().to_le_bytes());
+        header[8..12].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        header[12..16].copy_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.writer.write_all(&header).map_err(write_error)?;
+        self.writer.write_all(frame).map_err(write_error)
+    }
+
+    pub fn write_goose_packet(&mut self, timestamp: SystemTime, pkt: &IECGoosePacket) -> Result<(),GooseError>{
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.resize(sizeGoosePacket(pkt), 0);
+        let result = encodeGoosePacket(pkt, &mut buffer, 0).and_then(|size| self.write_frame(timestamp, &buffer[..size]));
+        self.buffer = buffer;
+        result
+    }
+
+    pub fn flush(&mut self) -> Result<(),GooseError>{
+        self.writer.flush().map_err(write_error)
+    }
+
+    pub fn into_inner(self) -> W{
+        self.writer
+    }
+}
+
+// lets a GoosePublisher record straight to a capture file, frames are stamped with the system time
+impl<W: Write> GooseSink for PcapWriter<W> {
+    fn send(&mut self, frame: &[u8]) -> Result<(),GooseError>{
+        self.write_frame(SystemTime::now(), frame)
+    }
+}
+
+fn write_error(e: io::Error) -> GooseError{
+    GooseError{ message: e.to_string(), pos: 0}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // section header, an interface with if_tsresol and one empty enhanced packet block
+    fn pcapng(tsresol: u8, ticks: u64) -> Vec<u8>{
+        let mut file = vec![];
+        for word in [PCAPNG_SECTION_HEADER, 28, PCAPNG_BYTE_ORDER_MAGIC, 1, u32::MAX, u32::MAX, 28]{
+            file.extend_from_slice(&word.to_le_bytes());
+        }
+        for word in [PCAPNG_INTERFACE_DESCRIPTION, 32, LINKTYPE_ETHERNET as u32, 65535, 0x0001_0009, tsresol as u32, 0, 32]{
+            file.extend_from_slice(&word.to_le_bytes());
+        }
+        for word in [PCAPNG_ENHANCED_PACKET, 32, 0, (ticks >> 32) as u32, ticks as u32, 0, 0, 32]{
+            file.extend_from_slice(&word.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn pcapng_timestamp_resolution(){
+        let mut reader = PcapReader::new(io::Cursor::new(pcapng(3, 1_500))).unwrap();
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!(record.timestamp, UNIX_EPOCH + Duration::from_millis(1_500));
+    }
+
+    #[test]
+    fn pcapng_timestamp_out_of_range(){
+        let mut reader = PcapReader::new(io::Cursor::new(pcapng(0, u64::MAX))).unwrap();
+        let e = reader.next_record().unwrap_err();
+        assert_eq!(e.pos, 60);
+        assert!(e.message.contains("out of range"), "{}", e.message);
+    }
+}
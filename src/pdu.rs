@@ -6,32 +6,124 @@ pub use crate::types::{*};
 
 use crate::pdu_encoder::{*};
 use crate::pdu_decoder::{*};
+use crate::sv_encoder::{*};
+use crate::sv_decoder::{*};
+use crate::basic_decoder::check_bounds;
+use crate::stream_encoder::{GooseSizeTree,fillIECGoosePduToSlice};
+use crate::security::{GooseKey,sizeAuthenticationExtension,encodeAuthenticationExtension};
+
+pub const GOOSE_ETHER_TYPE:[u8;2] = [0x88, 0xb8];
+pub const SV_ETHER_TYPE:[u8;2] = [0x88, 0xba];
+pub const VLAN_ETHER_TYPE:[u8;2] = [0x81, 0x00];
+pub const SERVICE_VLAN_ETHER_TYPE:[u8;2] = [0x88, 0xa8];
+pub const HSR_ETHER_TYPE:[u8;2] = [0x89, 0x2f];
+
+// reserved 1 bits holding the length of the IEC 62351-6 extension behind the pdu
+pub const SECURITY_LENGTH_MASK:u16 = 0x0fff;
+pub const SIMULATION_BIT:u16 = 0x8000;
+
+// where the parts of a decoded frame are, needed to check the authentication extension
+#[derive(Debug,Clone,Copy)]
+pub(crate) struct GooseFrameLayout {
+    pub appid_pos: usize,
+    pub pdu_end: usize,
+    pub extension_length: usize,
+}
+
+// the pdu is sized once and written in a single pass by the stream encoder
+pub fn encodeGoosePacket(pkt: &IECGoosePacket, buffer: &mut[u8], pos:usize) -> Result<usize,GooseError>{
+    let goose_hdr = simulationHeader(pkt)?;
+    let tree = GooseSizeTree::new(&pkt.pdu);
+    let frame_size = sizeFrame(&pkt.eth_hdr, &pkt.prp, tree.frame_size(), 0);
+    if pos + frame_size > buffer.len(){
+        return Err(GooseError{ message: format!("buffer too short, frame needs {} bytes", frame_size), pos});
+    }
+    encodeGooseFrame(&pkt.eth_hdr, &goose_hdr, GOOSE_ETHER_TYPE, &pkt.prp, None, buffer, pos, |buffer, pos| fillIECGoosePduToSlice(&pkt.pdu, &tree, buffer, pos))
+}
+
+// exact number of bytes encodeGoosePacket writes for this packet
+pub fn sizeGoosePacket(pkt: &IECGoosePacket) -> usize{
+    sizeFrame(&pkt.eth_hdr, &pkt.prp, sizeGoosePduFrame(&pkt.pdu), 0)
+}
+
+pub fn encodeSVPacket(pkt: &IECSVPacket, buffer: &mut[u8], pos:usize) -> Result<usize,GooseError>{
+    let frame_size = sizeSVPacket(pkt);
+    if pos + frame_size > buffer.len(){
+        return Err(GooseError{ message: format!("buffer too short, frame needs {} bytes", frame_size), pos});
+    }
+    encodeGooseFrame(&pkt.eth_hdr, &pkt.sv_hdr, SV_ETHER_TYPE, &pkt.prp, None, buffer, pos, |buffer, pos| encodeIECSVPdu(&pkt.pdu, buffer, pos))
+}
+
+// exact number of bytes encodeSVPacket writes for this packet
+pub fn sizeSVPacket(pkt: &IECSVPacket) -> usize{
+    sizeFrame(&pkt.eth_hdr, &pkt.prp, sizeSVPduFrame(&pkt.pdu), 0)
+}
+
+// the simulation flag is carried twice, in reserved 1 and in the goosePdu. a header bit left
+// unset follows the goosePdu, only a header claiming simulation for a goosePdu that does not
+// is a contradiction
+pub fn checkSimulation(pkt: &IECGoosePacket) -> Result<(),GooseError>{
+    if pkt.goose_hdr.simulation && !pkt.pdu.simulation{
+        return Err(GooseError{ message: "header simulation true does not match goosePdu simulation false".into(), pos: 0});
+    }
+    Ok(())
+}
 
-use std::time::{SystemTime, UNIX_EPOCH};
+// the APPID header as encoded, with the simulation bit taken over from the goosePdu
+pub(crate) fn simulationHeader(pkt: &IECGoosePacket) -> Result<IECGooseHeader,GooseError>{
+    checkSimulation(pkt)?;
+    Ok(IECGooseHeader{ simulation: pkt.pdu.simulation, ..pkt.goose_hdr.clone() })
+}
 
-pub fn encodeGoosePacket(pkt: &IECGoosePacket, buffer: &mut[u8], pos:usize) -> usize{
-    encodeGooseFrame(&pkt.eth_hdr, &pkt.goose_hdr, &pkt.pdu, &pkt.prp, buffer, pos)
+pub(crate) fn sizeFrame(header: & EthernetHeader, prp: &Option<IECPRP1>, pdu_size: usize, extension_size: usize) -> usize{
+    let mut size = sizeEthernetHeader(header) + IECGooseHeader::getSize() + pdu_size + extension_size;
+    if prp.is_some(){
+        size += IECPRP1::getSize();
+    }
+    size
 }
 
-fn encodeGooseFrame(header: & EthernetHeader, goose_header: &IECGooseHeader, pdu: & IECGoosePdu, prp: &Option<IECPRP1>, buffer: &mut[u8], pos:usize) ->usize{
+// Ethernet header, APPID header, authentication extension and PRP trailer around a GOOSE or SV pdu written by encode_pdu
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn encodeGooseFrame<F>(header: & EthernetHeader, goose_header: &IECGooseHeader, ether_type: [u8;2], prp: &Option<IECPRP1>, key: Option<&GooseKey>, buffer: &mut[u8], pos:usize, encode_pdu: F) ->Result<usize,GooseError>
+    where F: FnOnce(&mut[u8], usize) -> Result<usize,GooseError>{
     let mut new_pos;
 
     let hdr_pos = encodeEthernetHeader(header,buffer,pos);
     new_pos = hdr_pos;
-    new_pos = encodeIECGoosePdu(pdu,buffer,new_pos + IECGooseHeader::getSize());
+    new_pos = encode_pdu(buffer,new_pos + IECGooseHeader::getSize())?;
     let goose_length = new_pos - hdr_pos - IECGooseHeader::getSize();
-    encodeGooseHeader(goose_header, buffer, hdr_pos, goose_length as u16);
+    if goose_length + 8 > u16::MAX as usize{
+        return Err(GooseError{ message: format!("pdu too large ({} bytes)", goose_length), pos: hdr_pos});
+    }
+    let extension_size = key.map_or(0, sizeAuthenticationExtension);
+    encodeGooseHeader(goose_header, ether_type, buffer, hdr_pos, goose_length as u16, extension_size as u16);
+
+    if let Some(key) = key{
+        // the authentication value covers APPID up to the end of the pdu
+        new_pos = encodeAuthenticationExtension(key, buffer, hdr_pos + 2, new_pos);
+    }
+
+    if header.HSR.is_some(){
+        // the HSR tag sits right before the EtherType, its LSDU size counts from the path field
+        let path_pos = hdr_pos - 4;
+        let fsize = ((buffer[path_pos] as u16 & 0xf0) << 8) | ((new_pos - path_pos) as u16 & 0x0fff);
+        buffer[path_pos..path_pos+2].copy_from_slice(&fsize.to_be_bytes());
+    }
 
     if let Some(prp) = prp{
-        let frame_size = if header.VLANID.is_some(){
-            new_pos - hdr_pos + 4
-        }
-        else{
-            new_pos - hdr_pos
-        };
+        let frame_size = new_pos - hdr_pos + header.tagsSize();
         new_pos = encodeIECPRP1(&prp, buffer, frame_size as u16, new_pos);
     }
-    new_pos
+    Ok(new_pos)
+}
+
+pub(crate) fn sizeEthernetHeader(header: & EthernetHeader) ->usize{
+    let mut size = 12 + header.tagsSize();
+    if header.HSR.is_some(){
+        size += IECHSR::getSize();
+    }
+    size
 }
 
 fn encodeEthernetHeader(header: & EthernetHeader, buffer: &mut[u8], pos:usize) ->usize{
@@ -44,21 +136,41 @@ fn encodeEthernetHeader(header: & EthernetHeader, buffer: &mut[u8], pos:usize) -
     buffer[new_pos..new_pos+6].copy_from_slice(&header.srcAddr);
     new_pos=new_pos+6;
 
-    if let Some(vlanid) = header.VLANID{
-        buffer[new_pos..new_pos+2].copy_from_slice(&[0x81,0x00]);
+    if let Some(outer) = header.outerVLAN{
+        buffer[new_pos..new_pos+2].copy_from_slice(&SERVICE_VLAN_ETHER_TYPE);
+        new_pos+=2;
+
+        buffer[new_pos..new_pos+2].copy_from_slice(&outer.to_tci().to_be_bytes());
+        new_pos+=2;
+    }
+
+    if let Some(vlan) = header.VLAN{
+        buffer[new_pos..new_pos+2].copy_from_slice(&VLAN_ETHER_TYPE);
         new_pos=new_pos+2;
 
-        buffer[new_pos..new_pos+2].copy_from_slice(&vlanid.to_be_bytes());
+        buffer[new_pos..new_pos+2].copy_from_slice(&vlan.to_tci().to_be_bytes());
         new_pos=new_pos+2;
     }
 
+    if let Some(hsr) = &header.HSR{
+        buffer[new_pos..new_pos+2].copy_from_slice(&HSR_ETHER_TYPE);
+        new_pos+=2;
+
+        // the LSDU size is filled in by encodeGooseFrame once the payload is written
+        buffer[new_pos..new_pos+2].copy_from_slice(&(((hsr.path as u16) & 0x0f) << 12).to_be_bytes());
+        new_pos+=2;
+
+        buffer[new_pos..new_pos+2].copy_from_slice(&hsr.sequence.to_be_bytes());
+        new_pos+=2;
+    }
+
     new_pos
 }
 
-fn encodeGooseHeader(header: &IECGooseHeader, buffer: &mut[u8], pos:usize, goose_len: u16) ->usize{
+fn encodeGooseHeader(header: &IECGooseHeader, ether_type: [u8;2], buffer: &mut[u8], pos:usize, goose_len: u16, security_length: u16) ->usize{
     let mut new_pos=pos;
 
-    buffer[new_pos..new_pos+2].copy_from_slice(&[0x88, 0xb8]);
+    buffer[new_pos..new_pos+2].copy_from_slice(&ether_type);
     new_pos+=2;
     // Start of GOOSE length
     buffer[new_pos..new_pos+2].copy_from_slice(&header.APPID);
@@ -67,10 +179,14 @@ fn encodeGooseHeader(header: &IECGooseHeader, buffer: &mut[u8], pos:usize, goose
     buffer[new_pos..new_pos+2].copy_from_slice(&(goose_len+8).to_be_bytes()); //+8 to include this data from appid
     new_pos=new_pos+2;
 
-    buffer[new_pos..new_pos+2].copy_from_slice(&[0 ;2]); // reserved 1
+    let mut reserved1 = security_length & SECURITY_LENGTH_MASK;
+    if header.simulation{
+        reserved1 |= SIMULATION_BIT;
+    }
+    buffer[new_pos..new_pos+2].copy_from_slice(&reserved1.to_be_bytes()); // reserved 1
     new_pos=new_pos+2;
 
-    buffer[new_pos..new_pos+2].copy_from_slice(&[0 ;2]); // reserved 2
+    buffer[new_pos..new_pos+2].copy_from_slice(&header.reserved2.to_be_bytes()); // reserved 2
     new_pos=new_pos+2;
 
     new_pos
@@ -94,21 +210,10 @@ fn encodeIECPRP1(prp: &IECPRP1, buffer: &mut[u8], frame_size: u16, mut pos:usize
     pos + 2
 }
 
-pub fn getTimeMs()->[u8;8]{
-    let mut time_array=[0 as u8;8];
-    let start = SystemTime::now();
-    let since_the_epoch = start
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards");
-    //println!("{:?}", since_the_epoch);
-    let seconds= since_the_epoch.as_secs() as u32;
-    let sec_array=seconds.to_be_bytes();
-    let subsec_nano=(since_the_epoch.subsec_micros() as f32 * 4294.967296) as u32;
-    let nano_array=subsec_nano.to_be_bytes();
-    time_array[0..4].copy_from_slice(&sec_array);
-    time_array[4..7].copy_from_slice(&nano_array[..3]);
-    time_array[7]=0x18;
-    time_array
+pub fn getTimeMs()->UtcTime{
+    let mut time=UtcTime::now();
+    time.quality=TimeQuality::from_byte(0x18);
+    time
 }
 
 pub fn display_buffer( buffer: &[u8], size:usize){
@@ -124,53 +229,146 @@ pub fn display_buffer( buffer: &[u8], size:usize){
     print!("\n");
 }
 
-fn decodeIECPRP1(buffer: &[u8], pos: &mut usize) -> Result<Option<IECPRP1>, GooseError>{
-    if (buffer.len() - *pos == 6) && buffer[*pos+4] == 0x88 && buffer[*pos+5] == 0xfb {
-        let seq = u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap());
-        *pos += 2;
-        let fsize = u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap());
-        match fsize >> 12{
-            0b1010 => Ok(Some(IECPRP1{ sequence: seq, lan: IECPRPLAN::LAN_A, frame_size: fsize & 0x0fff })),
-            0b1011 => Ok(Some(IECPRP1{ sequence: seq, lan: IECPRPLAN::LAN_B, frame_size: fsize & 0x0fff })),
-            _=> Err(GooseError{message: "IEC PRP LAN undefined".into(), pos: *pos})
+// the trailer follows the payload at pos, but padding may sit before it and an FCS after it, so
+// every 0x88FB suffix from the frame end back to pos is a candidate whose LAN id and LSDU size
+// have to match. padding of non PRP frames can hold 0x88FB as well, an inconsistent candidate
+// only fails the frame when it directly follows the payload with a valid LAN id.
+// lsdu_start is where the LSDU size counts from, the EtherType moved back by the VLAN tags
+fn decodeIECPRP1(buffer: &[u8], pos: &mut usize, lsdu_start: usize) -> Result<Option<IECPRP1>, GooseError>{
+    let mut end = buffer.len();
+    while end >= *pos + IECPRP1::getSize(){
+        let start = end - IECPRP1::getSize();
+        end -= 1;
+        if buffer[start+4..start+6] != [0x88, 0xfb]{
+            continue;
+        }
+
+        let seq = u16::from_be_bytes(buffer[start..start+2].try_into().unwrap());
+        let fsize = u16::from_be_bytes(buffer[start+2..start+4].try_into().unwrap());
+        let lan = match fsize >> 12{
+            0b1010 => IECPRPLAN::LAN_A,
+            0b1011 => IECPRPLAN::LAN_B,
+            _=> continue,
+        };
+
+        let lsdu_size = start - lsdu_start;
+        if fsize & 0x0fff != lsdu_size as u16 & 0x0fff{
+            if start == *pos{
+                return Err(GooseError{ message: format!("PRP LSDU size {} does not match the payload size {}", fsize & 0x0fff, lsdu_size), pos: start+2});
+            }
+            continue;
         }
+
+        *pos = start + IECPRP1::getSize();
+        return Ok(Some(IECPRP1{ sequence: seq, lan, frame_size: fsize & 0x0fff }));
     }
-    else{
-        Ok(None)
+    Ok(None)
+}
+
+// an authentication extension is skipped, use decodeSecureGoosePacket to verify it
+pub fn decodeGoosePacket(buffer: &[u8], pos:usize) -> Option<Result<IECGoosePacket,GooseError>>{
+    match decodeGooseFrame(buffer, pos){
+        Some(Ok((pkt, _))) => Some(Ok(pkt)),
+        Some(Err(e)) => Some(Err(e)),
+        None => None,
     }
 }
 
-pub fn decodeGoosePacket(buffer: &[u8], mut pos:usize) -> Option<Result<IECGoosePacket,GooseError>>{
+pub(crate) fn decodeGooseFrame(buffer: &[u8], pos:usize) -> Option<Result<(IECGoosePacket,GooseFrameLayout),GooseError>>{
+    match decodeFrame(buffer, pos, GOOSE_ETHER_TYPE, decodeIECGoosePdu)?{
+        Ok(frame) => Some(Ok((IECGoosePacket{eth_hdr: frame.eth_hdr, goose_hdr: frame.app_hdr, pdu: frame.pdu, prp: frame.prp}, frame.layout))),
+        Err(e) => Some(Err(e)),
+    }
+}
 
-    let header = match decodeEthernetHeader(buffer,&mut pos){
-        Ok(e) => e,
-        Err(e) => {return Some(Err(e));}
-    };
+// None when the frame is not SV, like decodeGoosePacket for non GOOSE frames
+pub fn decodeSVPacket(buffer: &[u8], pos:usize) -> Option<Result<IECSVPacket,GooseError>>{
+    match decodeFrame(buffer, pos, SV_ETHER_TYPE, decodeIECSVPdu)?{
+        Ok(frame) => Some(Ok(IECSVPacket{eth_hdr: frame.eth_hdr, sv_hdr: frame.app_hdr, pdu: frame.pdu, prp: frame.prp})),
+        Err(e) => Some(Err(e)),
+    }
+}
 
-    let goose_hedaer = match decodeGooseHeader(buffer, &mut pos) {
-        Some(Ok(h)) => h,
+pub(crate) struct DecodedFrame<P> {
+    pub eth_hdr: EthernetHeader,
+    pub app_hdr: IECGooseHeader,
+    pub pdu: P,
+    pub prp: Option<IECPRP1>,
+    pub layout: GooseFrameLayout,
+}
+
+// headers, the pdu read by decode_pdu, the authentication extension and the PRP trailer
+pub(crate) fn decodeFrame<'a, P, F>(buffer: &'a [u8], mut pos:usize, ether_type: [u8;2], decode_pdu: F) -> Option<Result<DecodedFrame<P>,GooseError>>
+    where F: FnOnce(&'a [u8], &mut usize) -> Result<P,GooseError>{
+
+    let (header, app_header, layout) = match decodeFrameHeaders(buffer, &mut pos, ether_type){
+        Some(Ok(headers)) => headers,
         Some(Err(e)) => {return Some(Err(e));},
         None => {return  None;},
     };
 
-    let pdu =match decodeIECGoosePdu(buffer, &mut pos){
+    let pdu =match decode_pdu(&buffer[..layout.pdu_end], &mut pos){
         Err(e) => {
             return Some(Err(e));
         }
         Ok(v) => {v}
     };
 
-    let prp = match decodeIECPRP1(buffer, &mut pos){
+    if layout.extension_length > 0{
+        pos = layout.pdu_end + layout.extension_length;
+    }
+
+    let prp = match decodeIECPRP1(buffer, &mut pos, layout.appid_pos - 2 - header.tagsSize()){
         Ok(v) => v,
         Err(e) => {return Some(Err(e));}
     };
 
-    Some(Ok(IECGoosePacket{eth_hdr: header, goose_hdr: goose_hedaer, pdu: pdu, prp: prp}))
+    Some(Ok(DecodedFrame{eth_hdr: header, app_hdr: app_header, pdu, prp, layout}))
+}
+
+// Ethernet and APPID headers, returns where the pdu ends according to the APPID length
+fn decodeFrameHeaders(buffer: &[u8], pos: &mut usize, ether_type: [u8;2]) -> Option<Result<(EthernetHeader,IECGooseHeader,GooseFrameLayout),GooseError>>{
+
+    let header = match decodeEthernetHeader(buffer,pos){
+        Ok(e) => e,
+        Err(e) => {return Some(Err(e));}
+    };
+
+    let goose_hedaer = match decodeGooseHeader(buffer, pos, ether_type) {
+        Some(Ok(h)) => h,
+        Some(Err(e)) => {return Some(Err(e));},
+        None => {return  None;},
+    };
+
+    // the length counts from APPID, the pdu may not run past it
+    let goose_end = *pos - 8 + goose_hedaer.length as usize;
+    if goose_end > buffer.len() || goose_end < *pos{
+        return Some(Err(GooseError{ message: format!("APPID length {} does not fit the frame", goose_hedaer.length), pos: *pos - 8}));
+    }
+
+    // reserved 1 announces the authentication extension following the pdu
+    let appid_pos = *pos - 8;
+    let extension_length = goose_hedaer.security_length as usize;
+    if check_bounds(buffer, goose_end, extension_length).is_err(){
+        return Some(Err(GooseError{ message: format!("security extension length {} does not fit the frame", extension_length), pos: appid_pos + 4}));
+    }
 
+    if let Some(hsr) = &header.HSR{
+        // path field, size and sequence, then the EtherType in front of APPID. the sender may
+        // count Ethernet padding behind the payload, up to the end of the frame
+        let path_pos = appid_pos - 6;
+        let lsdu_size = goose_end + extension_length - path_pos;
+        let frame_size = hsr.frame_size as usize;
+        if frame_size != lsdu_size & 0x0fff && !(lsdu_size..=buffer.len() - path_pos).contains(&frame_size){
+            return Some(Err(GooseError{ message: format!("HSR LSDU size {} does not match the payload size {}", hsr.frame_size, lsdu_size), pos: path_pos}));
+        }
+    }
+
+    Some(Ok((header, goose_hedaer, GooseFrameLayout{ appid_pos, pdu_end: goose_end, extension_length })))
 }
 
 fn decodeEthernetHeader(buffer: &[u8], pos: &mut usize) -> Result<EthernetHeader,GooseError>{
-    if *pos + 18 > buffer.len(){
+    if check_bounds(buffer, *pos, 18).is_err(){
         return Err(GooseError{ message: "Buffer too short".into(), pos: *pos});
     }
     let mut header = EthernetHeader::default();
@@ -180,27 +378,42 @@ fn decodeEthernetHeader(buffer: &[u8], pos: &mut usize) -> Result<EthernetHeader
     header.srcAddr.copy_from_slice(&buffer[*pos..*pos+6]);
     *pos+=6;
 
-    let mut ether_type = [0;2];
-    ether_type.copy_from_slice(&buffer[*pos..*pos+2]);
+    // Q-in-Q: an 802.1ad service tag may come before the 802.1Q tag
+    if buffer[*pos..*pos+2] == SERVICE_VLAN_ETHER_TYPE{
+        *pos += 2;
+        header.outerVLAN = Some(VLANTag::from_tci(u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap())));
+        *pos+=2;
+        check_bounds(buffer, *pos, 2)?;
+    }
 
-    if ether_type ==[0x81,0x00]{ //if vlan
+    if buffer[*pos..*pos+2] == VLAN_ETHER_TYPE{ //if vlan
+        check_bounds(buffer, *pos, 4)?;
         *pos += 2;
-        let vlanid = u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap());
-        header.VLANID = Some(vlanid);
+        header.VLAN = Some(VLANTag::from_tci(u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap())));
         *pos+=2;
+        check_bounds(buffer, *pos, 2)?;
+    }
+
+    if buffer[*pos..*pos+2] == HSR_ETHER_TYPE{
+        check_bounds(buffer, *pos, IECHSR::getSize() + 2)?;
+        *pos += 2;
+        let fsize = u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap());
+        let sequence = u16::from_be_bytes(buffer[*pos+2..*pos+4].try_into().unwrap());
+        header.HSR = Some(IECHSR{ path: (fsize >> 12) as u8, frame_size: fsize & 0x0fff, sequence });
+        *pos += 4;
     }
     Ok(header)
 }
 
-fn decodeGooseHeader(buffer: &[u8], pos: &mut usize) -> Option<Result<IECGooseHeader, GooseError>>{
-    if *pos + 10 > buffer.len(){
+fn decodeGooseHeader(buffer: &[u8], pos: &mut usize, expected_ether_type: [u8;2]) -> Option<Result<IECGooseHeader, GooseError>>{
+    if check_bounds(buffer, *pos, 10).is_err(){
         return Some(Err(GooseError{ message: "Buffer too short".into(), pos: *pos}));
     }
     let mut header =IECGooseHeader::default();
     let mut ether_type = [0;2];
     ether_type.copy_from_slice(&buffer[*pos..*pos+2]);
     *pos += 2;
-    if ether_type !=[0x88,0xb8]
+    if ether_type !=expected_ether_type
     {
         return None;
     }
@@ -211,8 +424,95 @@ fn decodeGooseHeader(buffer: &[u8], pos: &mut usize) -> Option<Result<IECGooseHe
     header.length= u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap());
     *pos+=2;
 
-    *pos+=2;  // reserved 1
-    *pos+=2;  // reserved 2
+    let reserved1 = u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap());
+    header.simulation = reserved1 & SIMULATION_BIT != 0;
+    header.security_length = reserved1 & SECURITY_LENGTH_MASK;
+    *pos+=2;
+
+    header.reserved2 = u16::from_be_bytes(buffer[*pos..*pos+2].try_into().unwrap());
+    *pos+=2;
 
     Some(Ok(header))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet() -> IECGoosePacket{
+        let mut pkt = IECGoosePacket::default();
+        pkt.pdu.allData = vec![IECData::boolean(true)];
+        pkt.pdu.numDatSetEntries = 1;
+        pkt
+    }
+
+    fn encode(pkt: &IECGoosePacket) -> Vec<u8>{
+        let mut buffer = vec![0_u8;sizeGoosePacket(pkt)];
+        let size = encodeGoosePacket(pkt, &mut buffer, 0).unwrap();
+        assert_eq!(size, buffer.len());
+        buffer
+    }
+
+    #[test]
+    fn padding_looking_like_prp_is_not_a_trailer(){
+        let mut frame = encode(&packet());
+        // bad LAN id, then a LAN id with the wrong size, neither right after the payload
+        frame.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x10, 0x05, 0x88, 0xfb, 0x00, 0x01, 0xa0, 0x05, 0x88, 0xfb]);
+        frame.resize(frame.len().max(60), 0x00);
+        let decoded = decodeGoosePacket(&frame, 0).unwrap().unwrap();
+        assert!(decoded.prp.is_none());
+    }
+
+    #[test]
+    fn prp_trailer_behind_padding(){
+        let mut pkt = packet();
+        pkt.prp = Some(IECPRP1{ sequence: 3, lan: IECPRPLAN::LAN_B, frame_size: 0 });
+        let mut frame = encode(&pkt);
+        frame.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        let decoded = decodeGoosePacket(&frame, 0).unwrap().unwrap();
+        assert_eq!(decoded.prp.unwrap().sequence, 3);
+
+        // the trailer right after the payload with a size that does not match
+        let mut frame = encode(&pkt);
+        let size_pos = frame.len() - 4;
+        frame[size_pos + 1] ^= 0x01;
+        assert!(decodeGoosePacket(&frame, 0).unwrap().is_err());
+    }
+
+    #[test]
+    fn hsr_size_counting_padding(){
+        let mut pkt = packet();
+        pkt.eth_hdr.HSR = Some(IECHSR{ path: 1, frame_size: 0, sequence: 5 });
+        let mut frame = encode(&pkt);
+        let path_pos = 14;
+        let lsdu_size = frame.len() - path_pos;
+
+        // padding behind the payload, counted or not by the HSR tag
+        frame.extend_from_slice(&[0x00;4]);
+        let padded_size = frame.len() - path_pos;
+        for size in [lsdu_size, padded_size]{
+            frame[path_pos..path_pos+2].copy_from_slice(&(0x1000 | size as u16).to_be_bytes());
+            let decoded = decodeGoosePacket(&frame, 0).unwrap().unwrap();
+            assert_eq!(decoded.eth_hdr.HSR.unwrap().sequence, 5);
+        }
+
+        // shorter than the payload or past the end of the frame
+        for size in [lsdu_size - 1, padded_size + 1]{
+            frame[path_pos..path_pos+2].copy_from_slice(&(0x1000 | size as u16).to_be_bytes());
+            assert!(decodeGoosePacket(&frame, 0).unwrap().is_err());
+        }
+    }
+
+    #[test]
+    fn header_simulation_follows_the_pdu(){
+        let mut pkt = packet();
+        pkt.pdu.simulation = true;
+        let decoded = decodeGoosePacket(&encode(&pkt), 0).unwrap().unwrap();
+        assert!(decoded.goose_hdr.simulation && decoded.pdu.simulation);
+
+        pkt.pdu.simulation = false;
+        pkt.goose_hdr.simulation = true;
+        let mut buffer = vec![0_u8;sizeGoosePacket(&pkt)];
+        assert!(encodeGoosePacket(&pkt, &mut buffer, 0).is_err());
+    }
+}
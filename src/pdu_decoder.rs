@@ -5,7 +5,14 @@ use crate::error::GooseError;
 use crate::types::{*};
 use crate::basic_decoder::{*};
 
+// deepest array/structure nesting accepted, keeps hostile frames from exhausting the stack
+pub const MAX_DATA_DEPTH:usize=32;
+
 pub fn decodeIECDataElement(buffer: &[u8], pos:usize) ->Result<(usize,IECData),GooseError>{
+    decodeIECDataElementNested(buffer, pos, 0)
+}
+
+fn decodeIECDataElementNested(buffer: &[u8], pos:usize, depth:usize) ->Result<(usize,IECData),GooseError>{
 
     let mut new_pos=pos;
 
@@ -16,29 +23,29 @@ pub fn decodeIECDataElement(buffer: &[u8], pos:usize) ->Result<(usize,IECData),G
     match  tag{
         0x83=> {
             let mut val:bool=false;
-            new_pos=decode_boolean(& mut val, buffer, new_pos);
+            new_pos=decode_boolean(& mut val, buffer, new_pos,length)?;
             return Ok((new_pos,IECData::boolean(val)));
         },
         0x85=>{
             match length{
                 1=>{
                     let mut val:i8=0;
-                    new_pos=decode_interger_8(& mut val, buffer, new_pos,length);
+                    new_pos=decode_interger_8(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int8 (val)));
                 },
                 2=>{
                     let mut val:i16=0;
-                    new_pos=decode_interger_16(& mut val, buffer, new_pos,length);
+                    new_pos=decode_interger_16(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int16 (val)));
                 },
                 3..=4=>{
                     let mut val:i32=0;
-                    new_pos=decode_interger(& mut val, buffer, new_pos,length);
+                    new_pos=decode_interger(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int32 (val)));
                 }
                 5..=8=>{
                     let mut val:i64=0;
-                    new_pos=decode_interger_64(& mut val, buffer, new_pos,length);
+                    new_pos=decode_interger_64(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int64 (val)));                },
                 _=>{
                     return Err(GooseError{
@@ -52,38 +59,29 @@ pub fn decodeIECDataElement(buffer: &[u8], pos:usize) ->Result<(usize,IECData),G
             match length{
                 1=>{
                     let mut val:u8=0;
-                    new_pos=decode_unsigned_8(& mut val, buffer, new_pos,length);
+                    new_pos=decode_unsigned_8(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int8u (val)));
                 },
                 2=>{
                     let mut val:u16=0;
-                    new_pos=decode_unsigned_16(& mut val, buffer, new_pos,length);
+                    new_pos=decode_unsigned_16(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int16u (val)));
                 },
                 3..=4=>{
                     let mut val:u32=0;
-                    new_pos=decode_unsigned(& mut val, buffer, new_pos,length);
+                    new_pos=decode_unsigned(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int32u (val)));
                 },
-                5=>{
-                    // only occur when 32bit unsigned prepend with zero
-                    if buffer[new_pos]!=0x00
-                    {
-                        return Err(GooseError{
-                            message:"oversize unsigned interger".to_string(),
-                            pos:new_pos
-                        });
-                    }
+                5 if buffer[new_pos]==0x00=>{
+                    // 32bit unsigned prepend with zero
                     let mut val:u32=0;
-                    new_pos=decode_unsigned(& mut val, buffer, new_pos+1,length-1);
+                    new_pos=decode_unsigned(& mut val, buffer, new_pos,length)?;
                     return Ok((new_pos,IECData::int32u (val)));
                 },
-                6..=8=>{
-                    // no support for u64
-                    return Err(GooseError{
-                        message:"oversize unsigned interger".to_string(),
-                        pos:new_pos
-                    });
+                5..=9=>{
+                    let mut val:u64=0;
+                    new_pos=decode_unsigned_64(& mut val, buffer, new_pos,length)?;
+                    return Ok((new_pos,IECData::int64u (val)));
                 },
                 _=>{
                     return Err(GooseError{
@@ -97,12 +95,12 @@ pub fn decodeIECDataElement(buffer: &[u8], pos:usize) ->Result<(usize,IECData),G
             match length{
                 5=>{
                     let mut val:f32=0.0;
-                    new_pos=decode_float(&mut val,buffer, new_pos, length);
+                    new_pos=decode_float(&mut val,buffer, new_pos, length)?;
                     return Ok((new_pos,IECData::float32(val)));
                 },
                 9=>{
                     let mut val:f64=0.0;
-                    new_pos=decode_float_64(&mut val,buffer, new_pos, length);
+                    new_pos=decode_float_64(&mut val,buffer, new_pos, length)?;
                     return Ok((new_pos,IECData::float64(val)));
                 },
                 _=>{
@@ -116,38 +114,44 @@ pub fn decodeIECDataElement(buffer: &[u8], pos:usize) ->Result<(usize,IECData),G
         },
         0x8a=>{
             let mut val:String="".to_string();
-            new_pos=decode_string(&mut val,buffer,new_pos,length);
+            new_pos=decode_string(&mut val,buffer,new_pos,length)?;
             return Ok((new_pos,IECData::visible_string (val)));
         },
         0x90=>{
             let mut val:String="".to_string();
-            new_pos=decode_string(&mut val,buffer,new_pos,length);
+            new_pos=decode_string(&mut val,buffer,new_pos,length)?;
             return Ok((new_pos,IECData::mms_string (val)));
         },
         0x84=>{
+            if length==0 {
+                return Err(GooseError{
+                    message:"zero length bit string".to_string(),
+                    pos:new_pos
+                });
+            }
             let mut padding:u8=0;
             let mut val:Vec<u8>=vec![0;length-1];
-            new_pos=decode_bit_string(&mut val,&mut padding,buffer,new_pos,length);
+            new_pos=decode_bit_string(&mut val,&mut padding,buffer,new_pos,length)?;
             return Ok((new_pos,IECData::bit_string {val,padding}));
         },
         0xa1=>{
             let mut val:Vec<IECData>=vec![];
-            new_pos=decodeIECData(&mut val,buffer,new_pos,new_pos+length)?;
+            new_pos=decodeIECDataNested(&mut val,buffer,new_pos,new_pos+length,depth+1)?;
             return Ok((new_pos,IECData::array (val)));
         },
         0xa2=>{
             let mut val:Vec<IECData>=vec![];
-            new_pos=decodeIECData(&mut val,buffer,new_pos,new_pos+length)?;
+            new_pos=decodeIECDataNested(&mut val,buffer,new_pos,new_pos+length,depth+1)?;
             return Ok((new_pos,IECData::structure (val)));
         },
         0x89=>{
             let mut val:Vec<u8>=vec![0;length];
-            new_pos=decode_octet_string(&mut val,buffer,new_pos,length);
+            new_pos=decode_octet_string(&mut val,buffer,new_pos,length)?;
             return Ok((new_pos,IECData::octet_string (val)));
         },
         0x91=>{
-            let mut val=[0 as u8;8];
-            new_pos=decode_octet_string(&mut val,buffer,new_pos,length);
+            let mut val=UtcTime::default();
+            new_pos=decode_utc_time(&mut val,buffer,new_pos,length)?;
             return Ok((new_pos,IECData::utc_time (val)));
         },
         _=>{
@@ -161,27 +165,56 @@ pub fn decodeIECDataElement(buffer: &[u8], pos:usize) ->Result<(usize,IECData),G
 }
 
 pub fn decodeIECData(data: &mut Vec<IECData>, buffer: &[u8], pos:usize, end:usize) ->Result<usize,GooseError>{
+    decodeIECDataNested(data, buffer, pos, end, 0)
+}
+
+fn decodeIECDataNested(data: &mut Vec<IECData>, buffer: &[u8], pos:usize, end:usize, depth:usize) ->Result<usize,GooseError>{
+
+    if depth>MAX_DATA_DEPTH {
+        return Err(GooseError{ message: "data nesting too deep".into(), pos});
+    }
+    if end>buffer.len() {
+        return Err(GooseError{ message: "data length exceeds buffer".into(), pos});
+    }
+    // elements may not run past the end of their parent
+    let buffer=&buffer[..end];
 
     let mut new_pos=pos;
 
-    loop {
-        let (next_pos, new_data)=decodeIECDataElement(buffer, new_pos)?;
+    while new_pos < end {
+        let (next_pos, new_data)=decodeIECDataElementNested(buffer, new_pos, depth)?;
         data.push(new_data);
         new_pos=next_pos;
-        if new_pos>= end {
-            break;
-        }
     }
 
     Ok(new_pos)
 }
 
-pub fn decodeIECGoosePdu(buffer: &[u8], pos: &mut usize) -> Result<IECGoosePdu,GooseError>{
+// goosePdu context tags, their field names and whether the field is required
+// goID is OPTIONAL, simulation and ndsCom are DEFAULT FALSE
+pub(crate) const GOOSE_PDU_FIELDS:[(u8,&str,bool);12]=[
+    (0x80,"gocbRef",true),
+    (0x81,"timeAllowedtoLive",true),
+    (0x82,"datSet",true),
+    (0x83,"goID",false),
+    (0x84,"t",true),
+    (0x85,"stNum",true),
+    (0x86,"sqNum",true),
+    (0x87,"simulation",false),
+    (0x88,"confRev",true),
+    (0x89,"ndsCom",false),
+    (0x8a,"numDatSetEntries",true),
+    (0xab,"allData",true),
+];
+
+// walks the goosePdu at pos and hands every field to decode_field(tag, buffer, value_pos, length),
+// which returns the position after the value. unknown, duplicate and missing fields are rejected
+// here so the owned and the borrowed decoder agree on what a valid goosePdu is
+pub(crate) fn decodeGoosePduFields<'a, F>(buffer: &'a [u8], pos: &mut usize, mut decode_field: F) -> Result<(),GooseError>
+    where F: FnMut(u8, &'a [u8], usize, usize) -> Result<usize,GooseError>{
     let mut tag:u8=0;
     let mut length:usize=0;
 
-    let mut pdu = IECGoosePdu::default();
-
     //goosePduLength
     *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
     if tag != 0x61{
@@ -190,51 +223,63 @@ pub fn decodeIECGoosePdu(buffer: &[u8], pos: &mut usize) -> Result<IECGoosePdu,G
     if length+*pos > buffer.len(){
         return Err(GooseError{ message: "buffer too short".into(), pos: *pos});
     }
+    let end=*pos+length;
+    // fields may not run past the end of the goosePdu
+    let buffer=&buffer[..end];
 
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_string(&mut pdu.gocbRef,buffer,*pos,length);
-
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_unsigned(&mut pdu.timeAllowedtoLive,buffer,*pos,length);
+    let mut seen=[false;12];
 
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_string(&mut pdu.datSet,buffer,*pos,length);
+    while *pos < end {
+        let field_pos=*pos;
+        *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
 
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_string(&mut pdu.goID,buffer,*pos,length);
-
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_octet_string(&mut pdu.t,buffer,*pos,length);
-
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_unsigned(&mut pdu.stNum,buffer,*pos,length);
-
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_unsigned(&mut pdu.sqNum,buffer,*pos,length);
-
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_boolean(&mut pdu.simulation,buffer,*pos);
+        let index=match GOOSE_PDU_FIELDS.iter().position(|(field_tag,_,_)| *field_tag==tag){
+            Some(index) => index,
+            None => {
+                return Err(GooseError{ message: format!("unexpected goosePdu tag 0x{:02x}",tag), pos: field_pos});
+            }
+        };
+        if seen[index]{
+            return Err(GooseError{ message: format!("duplicate goosePdu field {} (0x{:02x})",GOOSE_PDU_FIELDS[index].1,tag), pos: field_pos});
+        }
+        seen[index]=true;
 
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_unsigned(&mut pdu.confRev,buffer,*pos,length);
+        *pos=decode_field(tag,buffer,*pos,length)?;
+    }
 
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_boolean(&mut pdu.ndsCom,buffer,*pos);
+    for (index,(tag,name,required)) in GOOSE_PDU_FIELDS.iter().enumerate(){
+        if *required && !seen[index]{
+            return Err(GooseError{ message: format!("missing goosePdu field {} (0x{:02x})",name,tag), pos: *pos});
+        }
+    }
+    Ok(())
+}
 
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    *pos=decode_unsigned(&mut pdu.numDatSetEntries,buffer,*pos,length);
+pub fn decodeIECGoosePdu(buffer: &[u8], pos: &mut usize) -> Result<IECGoosePdu,GooseError>{
+    // IECGoosePdu::default() already holds the ASN.1 DEFAULT values of the optional fields
+    let mut pdu = IECGoosePdu::default();
 
-    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
-    if tag != 0xab{
-        return Err(GooseError{ message: "allData tag != ab".into(), pos: *pos});
-    }
-    *pos=decodeIECData(&mut pdu.allData,buffer,*pos,*pos+length)?;
+    decodeGoosePduFields(buffer, pos, |tag, buffer, pos, length| {
+        match tag{
+            0x80=>decode_string(&mut pdu.gocbRef,buffer,pos,length),
+            0x81=>decode_unsigned(&mut pdu.timeAllowedtoLive,buffer,pos,length),
+            0x82=>decode_string(&mut pdu.datSet,buffer,pos,length),
+            0x83=>decode_string(&mut pdu.goID,buffer,pos,length),
+            0x84=>decode_utc_time(&mut pdu.t,buffer,pos,length),
+            0x85=>decode_unsigned(&mut pdu.stNum,buffer,pos,length),
+            0x86=>decode_unsigned(&mut pdu.sqNum,buffer,pos,length),
+            0x87=>decode_boolean(&mut pdu.simulation,buffer,pos,length),
+            0x88=>decode_unsigned(&mut pdu.confRev,buffer,pos,length),
+            0x89=>decode_boolean(&mut pdu.ndsCom,buffer,pos,length),
+            0x8a=>decode_unsigned(&mut pdu.numDatSetEntries,buffer,pos,length),
+            _=>decodeIECData(&mut pdu.allData,buffer,pos,pos+length),
+        }
+    })?;
 
     if pdu.numDatSetEntries as usize != pdu.allData.len(){
-        eprintln!("all data len {} pdu {}", pdu.allData.len(), pdu.numDatSetEntries);
-        return Err(GooseError{ message: "allData size does not match data entries".into(), pos: *pos});
+        return Err(GooseError{ message: format!("allData size {} does not match data entries {}", pdu.allData.len(), pdu.numDatSetEntries), pos: *pos});
     }
 
     //print!("decode pdu: {:?}",pdu);
     Ok(pdu)
-}
\ No newline at end of file
+}
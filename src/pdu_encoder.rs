@@ -1,16 +1,23 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
+use crate::error::GooseError;
 use crate::types::{*};
 use crate::basic_encoder::{*};
 
-pub fn encodeIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8], pos:usize) ->usize{
+pub fn encodeIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8], pos:usize) ->Result<usize,GooseError>{
     // first pass cacluate the length
-    let (goosePduLength,dataSetSize)=sizeIECGoosePdu(pdu,buffer);
+    let (goosePduLength,dataSetSize)=sizeIECGoosePdu(pdu,&mut []);
     // second pass fill the buffer
     fillIECGoosePdu(pdu, buffer, pos, goosePduLength, dataSetSize)
 }
 
+// total number of bytes encodeIECGoosePdu writes, including the 0x61 tag and length
+pub fn sizeGoosePduFrame(pdu: & IECGoosePdu) ->usize{
+    let (goosePduLength,_)=sizeIECGoosePdu(pdu,&mut []);
+    1+size_length(goosePduLength)+goosePduLength
+}
+
 pub fn sizeIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8]) ->(usize,usize){
 
     let mut goosePduLength=0;
@@ -30,7 +37,7 @@ pub fn sizeIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8]) ->(usize,usize){
     size=encode_string(0x83, &pdu.goID, buffer, 0, fill);
     goosePduLength+=1+size_length(size)+size;
 
-    size=encode_octet_string(0x84, &pdu.t, buffer, 0, fill);
+    size=encode_utc_time(0x84, &pdu.t, buffer, 0, fill);
     goosePduLength+=1+size_length(size)+size;
 
     size=encode_unsigned(0x85, pdu.stNum, buffer, 0, fill);
@@ -58,7 +65,12 @@ pub fn sizeIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8]) ->(usize,usize){
 
 }
 
-pub fn fillIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8], pos:usize, goosePduLength: usize, dataSetSize: usize) ->usize{
+pub fn fillIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8], pos:usize, goosePduLength: usize, dataSetSize: usize) ->Result<usize,GooseError>{
+
+    let required=1+size_length(goosePduLength)+goosePduLength;
+    if pos+required > buffer.len(){
+        return Err(GooseError{ message: format!("buffer too short, goosePdu needs {} bytes", required), pos});
+    }
 
     let mut new_pos=pos;
     let fill= true;
@@ -75,7 +87,7 @@ pub fn fillIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8], pos:usize, goosePdu
 
     new_pos=encode_string(0x83, &pdu.goID, buffer, new_pos, fill);
 
-    new_pos=encode_octet_string(0x84, &pdu.t, buffer, new_pos, fill);
+    new_pos=encode_utc_time(0x84, &pdu.t, buffer, new_pos, fill);
 
     new_pos=encode_unsigned(0x85, pdu.stNum, buffer, new_pos, fill);
 
@@ -93,7 +105,7 @@ pub fn fillIECGoosePdu(pdu: & IECGoosePdu, buffer: &mut[u8], pos:usize, goosePdu
 
     new_pos=encodeIECData(pdu, buffer, new_pos);
 
-    new_pos
+    Ok(new_pos)
 
 }
 
@@ -122,6 +134,7 @@ pub fn sizeIECDataElement(data: & IECData, buffer: &mut[u8]) ->usize{
         IECData::int8u (val)=> encode_unsigned_general(0, &val.to_be_bytes(), buffer, 0, fill),
         IECData::int16u (val)=> encode_unsigned_general(0, &val.to_be_bytes(), buffer, 0, fill),
         IECData::int32u (val)=> encode_unsigned_general(0, &val.to_be_bytes(), buffer, 0, fill),
+        IECData::int64u (val)=> encode_unsigned_general(0, &val.to_be_bytes(), buffer, 0, fill),
 
         IECData::float32 (val)=> encode_float_general(0, &val.to_be_bytes(), buffer, 0, fill),
         IECData::float64 (val)=> encode_float_general(0, &val.to_be_bytes(), buffer, 0, fill),
@@ -132,7 +145,7 @@ pub fn sizeIECDataElement(data: & IECData, buffer: &mut[u8]) ->usize{
         IECData::array (val)=>encode_array(0,&val,buffer,0,fill),
         IECData::structure (val)=>encode_structure(0,&val,buffer,0,fill),
         IECData::octet_string (val)=>encode_octet_string(0,&val,buffer,0,fill),
-        IECData::utc_time (val)=>encode_octet_string(0,val,buffer,0,fill),
+        IECData::utc_time (val)=>encode_utc_time(0,val,buffer,0,fill),
         //_=>{println!("unkowntype in sizeIECDataElement");0}
     };
     //println!("length {},dataSetSize {}",size_length(dataSetSize),dataSetSize);
@@ -192,6 +205,7 @@ pub fn encodeIECDataElement(data: & IECData, buffer: &mut[u8], pos:usize) ->usiz
         IECData::int8u (val)=> encode_unsigned_general(0x86, &val.to_be_bytes(), buffer, new_pos, fill),
         IECData::int16u (val)=> encode_unsigned_general(0x86, &val.to_be_bytes(), buffer, new_pos, fill),
         IECData::int32u (val)=> encode_unsigned_general(0x86, &val.to_be_bytes(), buffer, new_pos, fill),
+        IECData::int64u (val)=> encode_unsigned_general(0x86, &val.to_be_bytes(), buffer, new_pos, fill),
 
         IECData::float32 (val)=> encode_float_general(0x87, &val.to_be_bytes(), buffer, new_pos, fill),
         IECData::float64 (val)=> encode_float_general(0x87, &val.to_be_bytes(), buffer, new_pos, fill),
@@ -202,7 +216,7 @@ pub fn encodeIECDataElement(data: & IECData, buffer: &mut[u8], pos:usize) ->usiz
         IECData::array(val)=>encode_array(0xa1,&val,buffer,new_pos,fill),
         IECData::structure(val)=>encode_structure(0xa2,&val,buffer,new_pos,fill),
         IECData::octet_string(val)=> encode_octet_string(0x89, &val, buffer, new_pos, fill),
-        IECData::utc_time(val)=> encode_octet_string(0x91, val, buffer, new_pos, fill),
+        IECData::utc_time(val)=> encode_utc_time(0x91, val, buffer, new_pos, fill),
         //_=>{panic!("unknown data type");}
     };
 
@@ -0,0 +1,341 @@
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+// borrowed view of a goosePdu: strings and octet strings point into the frame and allData
+// is decoded lazily, so a successful decode does not touch the heap
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::basic_decoder::{*};
+use crate::pdu_decoder::{decodeGoosePduFields,MAX_DATA_DEPTH};
+use crate::pdu::{decodeFrame,DecodedFrame,GOOSE_ETHER_TYPE};
+
+// string octets as sent. like the owned decoder, to_str_lossy replaces invalid UTF-8 with
+// U+FFFD, it only allocates for such strings
+#[derive(Debug,Default,Clone,Copy,PartialEq,Eq)]
+pub struct IECStrRef<'a>(&'a [u8]);
+
+impl<'a> IECStrRef<'a> {
+    pub fn as_bytes(&self) -> &'a [u8]{
+        self.0
+    }
+
+    pub fn to_str_lossy(&self) -> Cow<'a, str>{
+        String::from_utf8_lossy(self.0)
+    }
+}
+
+impl fmt::Display for IECStrRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        write!(f, "{}", self.to_str_lossy())
+    }
+}
+
+#[derive(Debug,Clone)]
+pub enum IECDataRef<'a>{
+    array(IECDataIter<'a>),
+    structure(IECDataIter<'a>),
+    boolean(bool),
+
+    int8(i8),
+    int16(i16),
+    int32(i32),
+    int64(i64),
+
+    int8u(u8),
+    int16u(u16),
+    int32u(u32),
+    int64u(u64),
+
+    float32(f32),
+    float64(f64),
+
+    visible_string(IECStrRef<'a>),
+    mms_string(IECStrRef<'a>),
+    // bytes as sent, IECData::bit_string holds them reversed
+    bit_string{ padding: u8, val: &'a [u8] },
+    octet_string(&'a [u8]),
+    utc_time(UtcTime)
+}
+
+impl IECDataRef<'_> {
+    // owned copy, decoding nested elements on the way
+    pub fn to_iec_data(&self) -> Result<IECData,GooseError>{
+        Ok(match self{
+            IECDataRef::array(iter) => IECData::array(iter.clone().map(|data| data?.to_iec_data()).collect::<Result<_,_>>()?),
+            IECDataRef::structure(iter) => IECData::structure(iter.clone().map(|data| data?.to_iec_data()).collect::<Result<_,_>>()?),
+            IECDataRef::boolean(val) => IECData::boolean(*val),
+            IECDataRef::int8(val) => IECData::int8(*val),
+            IECDataRef::int16(val) => IECData::int16(*val),
+            IECDataRef::int32(val) => IECData::int32(*val),
+            IECDataRef::int64(val) => IECData::int64(*val),
+            IECDataRef::int8u(val) => IECData::int8u(*val),
+            IECDataRef::int16u(val) => IECData::int16u(*val),
+            IECDataRef::int32u(val) => IECData::int32u(*val),
+            IECDataRef::int64u(val) => IECData::int64u(*val),
+            IECDataRef::float32(val) => IECData::float32(*val),
+            IECDataRef::float64(val) => IECData::float64(*val),
+            IECDataRef::visible_string(val) => IECData::visible_string(val.to_str_lossy().into_owned()),
+            IECDataRef::mms_string(val) => IECData::mms_string(val.to_str_lossy().into_owned()),
+            IECDataRef::bit_string{ padding, val } => IECData::bit_string{ padding: *padding, val: val.iter().rev().map(|byte| byte.reverse_bits()).collect() },
+            IECDataRef::octet_string(val) => IECData::octet_string(val.to_vec()),
+            IECDataRef::utc_time(val) => IECData::utc_time(*val),
+        })
+    }
+}
+
+// walks the elements of allData or of one array/structure, stops after the first error
+#[derive(Debug,Clone)]
+pub struct IECDataIter<'a>{
+    buffer: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> IECDataIter<'a> {
+    fn new(buffer: &'a [u8], depth: usize) -> IECDataIter<'a>{
+        IECDataIter{ buffer, pos: 0, depth }
+    }
+}
+
+impl<'a> Iterator for IECDataIter<'a> {
+    type Item = Result<IECDataRef<'a>,GooseError>;
+
+    fn next(&mut self) -> Option<Self::Item>{
+        if self.pos >= self.buffer.len(){
+            return None;
+        }
+        match decodeIECDataRefElement(self.buffer, self.pos, self.depth){
+            Ok((next_pos, data)) => {
+                self.pos = next_pos;
+                Some(Ok(data))
+            },
+            Err(e) => {
+                self.pos = self.buffer.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn decodeStr<'a>(value: &mut IECStrRef<'a>, buffer: &'a [u8], pos:usize, length:usize) -> Result<usize,GooseError>{
+    check_bounds(buffer,pos,length)?;
+    *value=IECStrRef(&buffer[pos..pos+length]);
+    Ok(pos+length)
+}
+
+fn decodeIECDataRefElement(buffer: &[u8], pos:usize, depth:usize) -> Result<(usize,IECDataRef<'_>),GooseError>{
+    let mut tag:u8=0;
+    let mut length:usize=0;
+    let new_pos=decode_tag_length(&mut tag,&mut length,buffer,pos)?;
+    let end=new_pos+length;
+
+    let data=match tag{
+        0x83=>{
+            let mut val=false;
+            decode_boolean(&mut val,buffer,new_pos,length)?;
+            IECDataRef::boolean(val)
+        },
+        0x85=>{
+            match length{
+                1=>{ let mut val=0; decode_interger_8(&mut val,buffer,new_pos,length)?; IECDataRef::int8(val) },
+                2=>{ let mut val=0; decode_interger_16(&mut val,buffer,new_pos,length)?; IECDataRef::int16(val) },
+                3..=4=>{ let mut val=0; decode_interger(&mut val,buffer,new_pos,length)?; IECDataRef::int32(val) },
+                5..=8=>{ let mut val=0; decode_interger_64(&mut val,buffer,new_pos,length)?; IECDataRef::int64(val) },
+                _=>{ return Err(GooseError{ message: "oversize signed interger".into(), pos: new_pos }); }
+            }
+        },
+        0x86=>{
+            match length{
+                1=>{ let mut val=0; decode_unsigned_8(&mut val,buffer,new_pos,length)?; IECDataRef::int8u(val) },
+                2=>{ let mut val=0; decode_unsigned_16(&mut val,buffer,new_pos,length)?; IECDataRef::int16u(val) },
+                3..=4=>{ let mut val=0; decode_unsigned(&mut val,buffer,new_pos,length)?; IECDataRef::int32u(val) },
+                // 32bit unsigned prepend with zero
+                5 if buffer[new_pos]==0x00=>{ let mut val=0; decode_unsigned(&mut val,buffer,new_pos,length)?; IECDataRef::int32u(val) },
+                5..=9=>{ let mut val=0; decode_unsigned_64(&mut val,buffer,new_pos,length)?; IECDataRef::int64u(val) },
+                _=>{ return Err(GooseError{ message: "oversize unsigned interger".into(), pos: new_pos }); }
+            }
+        },
+        0x87=>{
+            match length{
+                5=>{ let mut val=0.0; decode_float(&mut val,buffer,new_pos,length)?; IECDataRef::float32(val) },
+                9=>{ let mut val=0.0; decode_float_64(&mut val,buffer,new_pos,length)?; IECDataRef::float64(val) },
+                _=>{ return Err(GooseError{ message: "unexpexted size float".into(), pos: new_pos }); }
+            }
+        },
+        0x8a=>IECDataRef::visible_string(IECStrRef(&buffer[new_pos..end])),
+        0x90=>IECDataRef::mms_string(IECStrRef(&buffer[new_pos..end])),
+        0x84=>{
+            if length==0 {
+                return Err(GooseError{ message: "zero length bit string".into(), pos: new_pos });
+            }
+            let padding=buffer[new_pos];
+            if padding>7 || (length==1 && padding!=0) {
+                return Err(GooseError{ message: format!("invalid bit string padding {}",padding), pos: new_pos });
+            }
+            IECDataRef::bit_string{ padding, val: &buffer[new_pos+1..end] }
+        },
+        0xa1 | 0xa2=>{
+            if depth+1>MAX_DATA_DEPTH {
+                return Err(GooseError{ message: "data nesting too deep".into(), pos: new_pos });
+            }
+            let iter=IECDataIter::new(&buffer[new_pos..end], depth+1);
+            if tag==0xa1 {IECDataRef::array(iter)} else {IECDataRef::structure(iter)}
+        },
+        0x89=>IECDataRef::octet_string(&buffer[new_pos..end]),
+        0x91=>{
+            let mut val=UtcTime::default();
+            decode_utc_time(&mut val,buffer,new_pos,length)?;
+            IECDataRef::utc_time(val)
+        },
+        _=>{
+            return Err(GooseError{ message: "unknown data type".into(), pos: new_pos });
+        }
+    };
+
+    Ok((end,data))
+}
+
+#[derive(Debug,Clone)]
+pub struct GoosePduRef<'a>{
+    pub gocbRef: IECStrRef<'a>,
+    pub timeAllowedtoLive: u32,
+    pub datSet: IECStrRef<'a>,
+    pub goID: IECStrRef<'a>,
+    pub t: UtcTime,
+    pub stNum: u32,
+    pub sqNum: u32,
+    pub simulation: bool,
+    pub confRev: u32,
+    pub ndsCom: bool,
+    pub numDatSetEntries: u32,
+    all_data: &'a [u8],
+}
+
+impl<'a> GoosePduRef<'a> {
+    pub fn allData(&self) -> IECDataIter<'a>{
+        IECDataIter::new(self.all_data, 0)
+    }
+
+    pub fn to_owned_pdu(&self) -> Result<IECGoosePdu,GooseError>{
+        Ok(IECGoosePdu{
+            gocbRef: self.gocbRef.to_str_lossy().into_owned(),
+            timeAllowedtoLive: self.timeAllowedtoLive,
+            datSet: self.datSet.to_str_lossy().into_owned(),
+            goID: self.goID.to_str_lossy().into_owned(),
+            t: self.t,
+            stNum: self.stNum,
+            sqNum: self.sqNum,
+            simulation: self.simulation,
+            confRev: self.confRev,
+            ndsCom: self.ndsCom,
+            numDatSetEntries: self.numDatSetEntries,
+            allData: self.allData().map(|data| data?.to_iec_data()).collect::<Result<_,_>>()?,
+        })
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct GoosePacketRef<'a>{
+    pub eth_hdr: EthernetHeader,
+    pub goose_hdr: IECGooseHeader,
+    pub pdu: GoosePduRef<'a>,
+    pub prp: Option<IECPRP1>,
+}
+
+pub fn decodeGoosePduRef<'a>(buffer: &'a [u8], pos: &mut usize) -> Result<GoosePduRef<'a>,GooseError>{
+    let mut pdu = GoosePduRef{
+        gocbRef: IECStrRef::default(), timeAllowedtoLive: 0, datSet: IECStrRef::default(), goID: IECStrRef::default(), t: UtcTime::default(),
+        stNum: 0, sqNum: 0, simulation: false, confRev: 0, ndsCom: false, numDatSetEntries: 0, all_data: &[],
+    };
+
+    decodeGoosePduFields(buffer, pos, |tag, buffer, pos, length| {
+        match tag{
+            0x80=>decodeStr(&mut pdu.gocbRef,buffer,pos,length),
+            0x81=>decode_unsigned(&mut pdu.timeAllowedtoLive,buffer,pos,length),
+            0x82=>decodeStr(&mut pdu.datSet,buffer,pos,length),
+            0x83=>decodeStr(&mut pdu.goID,buffer,pos,length),
+            0x84=>decode_utc_time(&mut pdu.t,buffer,pos,length),
+            0x85=>decode_unsigned(&mut pdu.stNum,buffer,pos,length),
+            0x86=>decode_unsigned(&mut pdu.sqNum,buffer,pos,length),
+            0x87=>decode_boolean(&mut pdu.simulation,buffer,pos,length),
+            0x88=>decode_unsigned(&mut pdu.confRev,buffer,pos,length),
+            0x89=>decode_boolean(&mut pdu.ndsCom,buffer,pos,length),
+            0x8a=>decode_unsigned(&mut pdu.numDatSetEntries,buffer,pos,length),
+            _=>{ pdu.all_data=&buffer[pos..pos+length]; Ok(pos+length) },
+        }
+    })?;
+
+    let mut tag:u8=0;
+    let mut length:usize=0;
+    // only the top level elements are walked here, nested ones are checked when iterated
+    let mut entries=0;
+    let mut data_pos=0;
+    while data_pos < pdu.all_data.len(){
+        data_pos=decode_tag_length(&mut tag,&mut length,pdu.all_data,data_pos)?+length;
+        entries+=1;
+    }
+    if pdu.numDatSetEntries as usize != entries{
+        return Err(GooseError{ message: format!("allData size {} does not match data entries {}", entries, pdu.numDatSetEntries), pos: *pos});
+    }
+
+    Ok(pdu)
+}
+
+// None for non GOOSE frames, like decodeGoosePacket
+pub fn decodeGoosePacketRef(buffer: &[u8], pos:usize) -> Option<Result<GoosePacketRef<'_>,GooseError>>{
+    match decodeFrame(buffer, pos, GOOSE_ETHER_TYPE, decodeGoosePduRef)?{
+        Ok(DecodedFrame{ eth_hdr, app_hdr, pdu, prp, .. }) => Some(Ok(GoosePacketRef{ eth_hdr, goose_hdr: app_hdr, pdu, prp })),
+        Err(e) => Some(Err(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu_decoder::decodeIECGoosePdu;
+    use crate::pdu_encoder::{encodeIECGoosePdu,sizeGoosePduFrame};
+
+    fn encode(pdu: &IECGoosePdu) -> Vec<u8>{
+        let mut buffer = vec![0_u8;sizeGoosePduFrame(pdu)];
+        encodeIECGoosePdu(pdu, &mut buffer, 0).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn invalid_utf8_decodes_like_the_owned_decoder(){
+        let pdu = IECGoosePdu{ gocbRef: "gcb\u{e9}".into(), allData: vec![IECData::visible_string("ab\u{e9}".into())], numDatSetEntries: 1, ..Default::default() };
+        let mut buffer = encode(&pdu);
+        // cut the two octet é in gocbRef and the visible string down to a lone lead octet
+        for (tag, pos) in [(0x80, 2), (0x8a, buffer.len() - 6)]{
+            assert_eq!(buffer[pos], tag);
+            let end = pos + 2 + buffer[pos + 1] as usize;
+            buffer[end - 1] = 0xff;
+        }
+
+        let owned = decodeIECGoosePdu(&buffer, &mut 0).unwrap();
+        let borrowed = decodeGoosePduRef(&buffer, &mut 0).unwrap();
+        assert_eq!(borrowed.gocbRef.as_bytes(), b"gcb\xc3\xff");
+        assert_eq!(borrowed.gocbRef.to_str_lossy(), owned.gocbRef);
+        assert_eq!(owned.gocbRef, "gcb\u{fffd}\u{fffd}");
+        assert_eq!(format!("{:?}", borrowed.to_owned_pdu().unwrap()), format!("{:?}", owned));
+    }
+
+    #[test]
+    fn field_checks_shared_with_the_owned_decoder(){
+        let buffer = encode(&IECGoosePdu::default());
+        // stNum tag turned into a second sqNum, then into an unknown tag
+        let stNum_pos = buffer.iter().position(|byte| *byte == 0x85).unwrap();
+        for (tag, message) in [(0x86, "duplicate goosePdu field sqNum"), (0x8f, "unexpected goosePdu tag 0x8f")]{
+            let mut buffer = buffer.clone();
+            buffer[stNum_pos] = tag;
+            let owned = decodeIECGoosePdu(&buffer, &mut 0).unwrap_err();
+            let borrowed = decodeGoosePduRef(&buffer, &mut 0).unwrap_err();
+            assert!(owned.message.starts_with(message), "{}", owned.message);
+            assert_eq!((owned.message, owned.pos), (borrowed.message, borrowed.pos));
+        }
+    }
+}
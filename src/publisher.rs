@@ -0,0 +1,315 @@
+#![allow(non_snake_case)]
+
+use std::time::{Duration, SystemTime};
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::frame_template::GooseFrameTemplate;
+
+// source of the current time, swap in a fake clock to drive the publisher in tests
+pub trait GooseClock {
+    fn now(&self) -> SystemTime;
+}
+
+#[derive(Debug,Default,Clone,Copy)]
+pub struct SystemClock;
+
+impl GooseClock for SystemClock {
+    fn now(&self) -> SystemTime{
+        SystemTime::now()
+    }
+}
+
+// where encoded frames go, a pnet channel, a pcap file or a Vec in tests
+pub trait GooseSink {
+    fn send(&mut self, frame: &[u8]) -> Result<(),GooseError>;
+}
+
+impl<F> GooseSink for F where F: FnMut(&[u8]) -> Result<(),GooseError> {
+    fn send(&mut self, frame: &[u8]) -> Result<(),GooseError>{
+        self(frame)
+    }
+}
+
+// IEC 61850-8-1 retransmission schedule: after a state change the interval starts at
+// min_time and grows by multiplier with every retransmission until it reaches max_time
+#[derive(Debug,Clone)]
+pub struct RetransmissionCurve {
+    pub min_time: Duration,
+    pub max_time: Duration,
+    pub multiplier: u32,
+}
+
+impl Default for RetransmissionCurve {
+    fn default() -> RetransmissionCurve{
+        RetransmissionCurve{ min_time: Duration::from_millis(4), max_time: Duration::from_millis(1000), multiplier: 2 }
+    }
+}
+
+impl RetransmissionCurve {
+    // interval between retransmission n and n+1 after a state change, n=0 is the change itself
+    pub fn interval(&self, retransmission: u32) -> Duration{
+        let mut interval = self.min_time;
+        for _ in 0..retransmission{
+            if interval >= self.max_time{
+                break;
+            }
+            interval = interval.saturating_mul(self.multiplier.max(1));
+        }
+        interval.min(self.max_time)
+    }
+
+    // subscribers declare the publisher lost after twice the longest interval
+    pub fn time_allowed_to_live(&self) -> u32{
+        u32::try_from(self.max_time.as_millis() * 2).unwrap_or(u32::MAX)
+    }
+}
+
+// stNum and sqNum roll over to 1, 0 is reserved for the first message after start up
+fn next_counter(value: u32) -> u32{
+    if value == u32::MAX { 1 } else { value + 1 }
+}
+
+pub struct GoosePublisher<C: GooseClock, S: GooseSink> {
+    packet: IECGoosePacket,
+    curve: RetransmissionCurve,
+    clock: C,
+    sink: S,
+    retransmission: u32,
+    next_send: Option<SystemTime>,
+    // encoded frame of the current dataset, retransmissions only patch stNum, sqNum and t
+    template: Option<GooseFrameTemplate>,
+    // state change the sink has not accepted yet
+    pending: Option<(IECGoosePacket, GooseFrameTemplate)>,
+}
+
+impl<C: GooseClock, S: GooseSink> GoosePublisher<C, S> {
+    // the packet is sent as it is on the first poll, stNum and sqNum are taken over from it
+    pub fn new(packet: IECGoosePacket, curve: RetransmissionCurve, clock: C, sink: S) -> GoosePublisher<C, S>{
+        GoosePublisher{ packet, curve, clock, sink, retransmission: 0, next_send: None, template: None, pending: None }
+    }
+
+    pub fn packet(&self) -> &IECGoosePacket{
+        &self.packet
+    }
+
+    pub fn sink(&self) -> &S{
+        &self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut S{
+        &mut self.sink
+    }
+
+    // time the next retransmission is due, None before the first poll
+    pub fn next_send(&self) -> Option<SystemTime>{
+        self.next_send
+    }
+
+    // new dataset values: bump stNum, reset sqNum, stamp t and send immediately. the new state is
+    // only taken over once the sink accepted it, a failed send is retried by the next poll
+    pub fn update(&mut self, allData: Vec<IECData>) -> Result<(),GooseError>{
+        let now = self.clock.now();
+        let mut packet = self.packet.clone();
+        let pdu = &mut packet.pdu;
+        pdu.numDatSetEntries = allData.len() as u32;
+        pdu.allData = allData;
+        pdu.stNum = next_counter(self.packet.pdu.stNum);
+        pdu.sqNum = 0;
+        pdu.t = UtcTime::from_system_time(now, pdu.t.quality)?;
+        pdu.timeAllowedtoLive = self.curve.time_allowed_to_live();
+        if let Some(template) = &self.template{
+            template.continue_sequences(&mut packet);
+        }
+        let template = GooseFrameTemplate::new(&packet)?;
+        self.pending = Some((packet, template));
+        self.transmit_pending(now)
+    }
+
+    // sends a retransmission when one is due and returns how long until the next one
+    pub fn poll(&mut self) -> Result<Duration,GooseError>{
+        let now = self.clock.now();
+        match self.next_send{
+            None => {
+                self.transmit(now, self.packet.pdu.sqNum)?;
+            },
+            Some(due) if due <= now && self.pending.is_some() => {
+                self.transmit_pending(now)?;
+            },
+            Some(due) if due <= now => {
+                self.transmit(now, next_counter(self.packet.pdu.sqNum))?;
+            },
+            Some(_) => {},
+        }
+        let due = self.next_send.unwrap_or(now);
+        Ok(due.duration_since(now).unwrap_or(Duration::ZERO))
+    }
+
+    // sqNum is only taken over once the sink accepted the frame, a failed send is retried with it
+    fn transmit(&mut self, now: SystemTime, sqNum: u32) -> Result<(),GooseError>{
+        let pdu = &self.packet.pdu;
+        let frame = match &mut self.template{
+            Some(template) => template.update(pdu.stNum, sqNum, &pdu.t),
+            None => {
+                self.packet.pdu.sqNum = sqNum;
+                self.packet.pdu.timeAllowedtoLive = self.curve.time_allowed_to_live();
+                self.template.insert(GooseFrameTemplate::new(&self.packet)?).frame()
+            },
+        };
+        self.sink.send(frame)?;
+
+        self.packet.pdu.sqNum = sqNum;
+        self.next_send = Some(now + self.curve.interval(self.retransmission));
+        self.retransmission = self.retransmission.saturating_add(1);
+        Ok(())
+    }
+
+    // sends the state change of update, it replaces packet and template once the sink accepted it
+    fn transmit_pending(&mut self, now: SystemTime) -> Result<(),GooseError>{
+        let Some((packet, template)) = self.pending.take() else {
+            return Ok(());
+        };
+        if let Err(e) = self.sink.send(template.frame()){
+            self.pending = Some((packet, template));
+            self.next_send = Some(now);
+            return Err(e);
+        }
+
+        self.packet = packet;
+        self.template = Some(template);
+        self.next_send = Some(now + self.curve.interval(0));
+        self.retransmission = 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::time::UNIX_EPOCH;
+    use crate::pdu::decodeGoosePacket;
+
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<SystemTime>>);
+
+    impl FakeClock {
+        fn advance(&self, by: Duration){
+            self.0.set(self.0.get() + by);
+        }
+    }
+
+    impl GooseClock for FakeClock {
+        fn now(&self) -> SystemTime{
+            self.0.get()
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeSink {
+        frames: Vec<IECGoosePdu>,
+        fail: bool,
+    }
+
+    impl GooseSink for FakeSink {
+        fn send(&mut self, frame: &[u8]) -> Result<(),GooseError>{
+            if self.fail{
+                return Err(GooseError{ message: "link down".into(), pos: 0 });
+            }
+            self.frames.push(decodeGoosePacket(frame, 0).unwrap()?.pdu);
+            Ok(())
+        }
+    }
+
+    fn publisher(stNum: u32, sqNum: u32) -> (FakeClock, GoosePublisher<FakeClock, FakeSink>){
+        let clock = FakeClock(Rc::new(Cell::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000))));
+        let mut packet = IECGoosePacket::default();
+        packet.pdu.stNum = stNum;
+        packet.pdu.sqNum = sqNum;
+        packet.pdu.allData = vec![IECData::boolean(false)];
+        packet.pdu.numDatSetEntries = 1;
+        let curve = RetransmissionCurve{ min_time: Duration::from_millis(4), max_time: Duration::from_millis(64), multiplier: 2 };
+        (clock.clone(), GoosePublisher::new(packet, curve, clock, FakeSink::default()))
+    }
+
+    fn counters(publisher: &GoosePublisher<FakeClock, FakeSink>) -> Vec<(u32, u32)>{
+        publisher.sink().frames.iter().map(|pdu| (pdu.stNum, pdu.sqNum)).collect()
+    }
+
+    #[test]
+    fn retransmission_curve(){
+        let (clock, mut publisher) = publisher(1, 0);
+        let mut intervals = vec![];
+        for _ in 0..7{
+            let wait = publisher.poll().unwrap();
+            intervals.push(wait.as_millis());
+            // nothing is due before the interval ran out
+            clock.advance(wait - Duration::from_millis(1));
+            assert_eq!(publisher.poll().unwrap(), Duration::from_millis(1));
+            clock.advance(Duration::from_millis(1));
+        }
+        assert_eq!(intervals, vec![4, 8, 16, 32, 64, 64, 64]);
+        assert_eq!(counters(&publisher), (0..7).map(|sqNum| (1, sqNum)).collect::<Vec<_>>());
+        assert!(publisher.sink().frames.iter().all(|pdu| pdu.timeAllowedtoLive == 128));
+
+        // a state change restarts the curve
+        publisher.update(vec![IECData::boolean(true)]).unwrap();
+        assert_eq!(publisher.poll().unwrap(), Duration::from_millis(4));
+        let last = publisher.sink().frames.last().unwrap();
+        assert_eq!((last.stNum, last.sqNum, last.timeAllowedtoLive), (2, 0, 128));
+        assert!(matches!(last.allData[0], IECData::boolean(true)));
+    }
+
+    #[test]
+    fn counters_roll_over_to_one(){
+        let (clock, mut publisher) = publisher(u32::MAX, u32::MAX - 1);
+        for _ in 0..3{
+            let wait = publisher.poll().unwrap();
+            clock.advance(wait);
+        }
+        publisher.update(vec![IECData::boolean(true)]).unwrap();
+        assert_eq!(counters(&publisher), vec![(u32::MAX, u32::MAX - 1), (u32::MAX, u32::MAX), (u32::MAX, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn failed_send_keeps_sqNum(){
+        let (clock, mut publisher) = publisher(1, 0);
+        let wait = publisher.poll().unwrap();
+        clock.advance(wait);
+
+        publisher.sink_mut().fail = true;
+        assert!(publisher.poll().is_err());
+        assert!(publisher.poll().is_err());
+        assert_eq!(publisher.packet().pdu.sqNum, 0);
+
+        publisher.sink_mut().fail = false;
+        publisher.poll().unwrap();
+        assert_eq!(counters(&publisher), vec![(1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn failed_update_is_retried(){
+        let (clock, mut publisher) = publisher(1, 0);
+        clock.advance(publisher.poll().unwrap());
+        publisher.poll().unwrap();
+
+        publisher.sink_mut().fail = true;
+        assert!(publisher.update(vec![IECData::boolean(true)]).is_err());
+        assert_eq!((publisher.packet().pdu.stNum, publisher.packet().pdu.sqNum), (1, 1));
+        // the state change is due again right away
+        assert_eq!(publisher.next_send(), Some(clock.now()));
+        assert!(publisher.poll().is_err());
+
+        publisher.sink_mut().fail = false;
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(publisher.poll().unwrap(), Duration::from_millis(4));
+        assert_eq!((publisher.packet().pdu.stNum, publisher.packet().pdu.sqNum), (2, 0));
+        let last = publisher.sink().frames.last().unwrap();
+        assert!(matches!(last.allData[0], IECData::boolean(true)));
+
+        clock.advance(Duration::from_millis(4));
+        publisher.poll().unwrap();
+        assert_eq!(counters(&publisher), vec![(1, 0), (1, 1), (2, 0), (2, 1)]);
+    }
+}
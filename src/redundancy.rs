@@ -0,0 +1,239 @@
+#![allow(non_snake_case)]
+
+// IEC 62439-3 duplicate discard: a node attached to both LANs of a PRP pair (or both ports of
+// an HSR ring) receives every frame twice and passes on the first copy only
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime};
+
+use crate::types::{*};
+
+#[derive(Debug,Default,Clone,PartialEq,Eq)]
+pub struct LANStatistics {
+    pub received: u64,
+    // second copies of frames already received on this or the other LAN
+    pub duplicates: u64,
+    // frames that only arrived on the other LAN before they aged out
+    pub missing: u64,
+    // sequence number behind the last one received on this LAN
+    pub out_of_order: u64,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum RedundancyResult {
+    // first copy of the frame, pass it on
+    Accepted(IECPRPLAN),
+    Duplicate(IECPRPLAN),
+    // no PRP trailer or HSR tag, nothing to discard against
+    NotRedundant,
+}
+
+impl RedundancyResult {
+    pub fn is_accepted(&self) -> bool{
+        !matches!(self, RedundancyResult::Duplicate(_))
+    }
+}
+
+#[derive(Debug,Clone)]
+struct SequenceEntry {
+    first_received: SystemTime,
+    seen: [bool;2],
+}
+
+#[derive(Debug,Default,Clone)]
+struct SourceState {
+    entries: HashMap<u16, SequenceEntry>,
+    // arrival order of the entries, oldest first
+    order: VecDeque<u16>,
+    last_sequence: [Option<u16>;2],
+}
+
+fn lanIndex(lan: IECPRPLAN) -> usize{
+    match lan{
+        IECPRPLAN::LAN_A => 0,
+        IECPRPLAN::LAN_B => 1,
+    }
+}
+
+// LAN and sequence number of a redundant frame, HSR reports the ring port in the lowest path bit
+pub fn redundancyInfo(pkt: &IECGoosePacket) -> Option<(IECPRPLAN, u16)>{
+    if let Some(prp) = &pkt.prp{
+        return Some((prp.lan, prp.sequence));
+    }
+    if let Some(hsr) = &pkt.eth_hdr.HSR{
+        let lan = if hsr.path & 0x01 == 0 {IECPRPLAN::LAN_A} else {IECPRPLAN::LAN_B};
+        return Some((lan, hsr.sequence));
+    }
+    None
+}
+
+#[derive(Debug)]
+pub struct DuplicateDiscard {
+    // sequence numbers remembered per source
+    window: usize,
+    // how long a sequence number waits for its copy from the other LAN
+    aging: Duration,
+    sources: HashMap<[u8;6], SourceState>,
+    statistics: [LANStatistics;2],
+}
+
+impl Default for DuplicateDiscard {
+    // 400 ms is the EntryForgetTime of IEC 62439-3
+    fn default() -> DuplicateDiscard{
+        DuplicateDiscard::new(256, Duration::from_millis(400))
+    }
+}
+
+impl DuplicateDiscard {
+    pub fn new(window: usize, aging: Duration) -> DuplicateDiscard{
+        DuplicateDiscard{ window: window.max(1), aging, sources: HashMap::new(), statistics: Default::default() }
+    }
+
+    pub fn statistics(&self, lan: IECPRPLAN) -> &LANStatistics{
+        &self.statistics[lanIndex(lan)]
+    }
+
+    pub fn on_packet(&mut self, pkt: &IECGoosePacket, now: SystemTime) -> RedundancyResult{
+        match redundancyInfo(pkt){
+            Some((lan, sequence)) => self.on_frame(pkt.eth_hdr.srcAddr, lan, sequence, now),
+            None => RedundancyResult::NotRedundant,
+        }
+    }
+
+    pub fn on_frame(&mut self, srcAddr: [u8;6], lan: IECPRPLAN, sequence: u16, now: SystemTime) -> RedundancyResult{
+        let index = lanIndex(lan);
+        let source = self.sources.entry(srcAddr).or_default();
+        Self::expire_source(source, &mut self.statistics, self.aging, now);
+
+        self.statistics[index].received += 1;
+
+        // wrapping distance, a negative one means the sequence went backwards on this LAN
+        if let Some(last) = source.last_sequence[index]{
+            if (sequence.wrapping_sub(last) as i16) < 0{
+                self.statistics[index].out_of_order += 1;
+            }
+        }
+        source.last_sequence[index] = Some(sequence);
+
+        if let Some(entry) = source.entries.get_mut(&sequence){
+            entry.seen[index] = true;
+            self.statistics[index].duplicates += 1;
+            return RedundancyResult::Duplicate(lan);
+        }
+
+        let mut seen = [false;2];
+        seen[index] = true;
+        source.entries.insert(sequence, SequenceEntry{ first_received: now, seen });
+        source.order.push_back(sequence);
+        while source.order.len() > self.window{
+            Self::forget_oldest(source, &mut self.statistics);
+        }
+
+        RedundancyResult::Accepted(lan)
+    }
+
+    // ages out entries without waiting for the next frame, to update the missing counters
+    pub fn expire(&mut self, now: SystemTime){
+        for source in self.sources.values_mut(){
+            Self::expire_source(source, &mut self.statistics, self.aging, now);
+        }
+        self.sources.retain(|_, source| !source.order.is_empty());
+    }
+
+    fn expire_source(source: &mut SourceState, statistics: &mut [LANStatistics;2], aging: Duration, now: SystemTime){
+        while let Some(sequence) = source.order.front(){
+            let expired = source.entries.get(sequence).is_none_or(|entry| entry.first_received + aging <= now);
+            if !expired{
+                break;
+            }
+            Self::forget_oldest(source, statistics);
+        }
+    }
+
+    fn forget_oldest(source: &mut SourceState, statistics: &mut [LANStatistics;2]){
+        let Some(sequence) = source.order.pop_front() else {
+            return;
+        };
+        if let Some(entry) = source.entries.remove(&sequence){
+            for (index, seen) in entry.seen.iter().enumerate(){
+                if !seen{
+                    statistics[index].missing += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    const SOURCE:[u8;6] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05];
+
+    fn at(ms: u64) -> SystemTime{
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_000 + ms)
+    }
+
+    #[test]
+    fn duplicate_within_window(){
+        let mut discard = DuplicateDiscard::default();
+        assert_eq!(discard.on_frame(SOURCE, IECPRPLAN::LAN_A, 10, at(0)), RedundancyResult::Accepted(IECPRPLAN::LAN_A));
+        assert_eq!(discard.on_frame(SOURCE, IECPRPLAN::LAN_B, 10, at(1)), RedundancyResult::Duplicate(IECPRPLAN::LAN_B));
+        // the same sequence number from another source is a different frame
+        assert!(discard.on_frame([0xff;6], IECPRPLAN::LAN_B, 10, at(1)).is_accepted());
+        assert_eq!(discard.on_frame(SOURCE, IECPRPLAN::LAN_B, 11, at(2)), RedundancyResult::Accepted(IECPRPLAN::LAN_B));
+        assert_eq!(discard.on_frame(SOURCE, IECPRPLAN::LAN_A, 11, at(3)), RedundancyResult::Duplicate(IECPRPLAN::LAN_A));
+    }
+
+    #[test]
+    fn window_wraps_around(){
+        let mut discard = DuplicateDiscard::new(4, Duration::from_millis(400));
+        // the sequence number wraps from 0xffff to 0 without counting as out of order
+        for sequence in [0xfffe, 0xffff, 0x0000, 0x0001]{
+            assert!(discard.on_frame(SOURCE, IECPRPLAN::LAN_A, sequence, at(0)).is_accepted());
+        }
+        assert_eq!(discard.on_frame(SOURCE, IECPRPLAN::LAN_B, 0xffff, at(1)), RedundancyResult::Duplicate(IECPRPLAN::LAN_B));
+        assert_eq!(discard.statistics(IECPRPLAN::LAN_A).out_of_order, 0);
+
+        // a fifth sequence number pushes 0xfffe out of the window, its late copy passes again
+        assert!(discard.on_frame(SOURCE, IECPRPLAN::LAN_A, 0x0002, at(2)).is_accepted());
+        assert_eq!(discard.on_frame(SOURCE, IECPRPLAN::LAN_B, 0xfffe, at(3)), RedundancyResult::Accepted(IECPRPLAN::LAN_B));
+        assert_eq!(discard.statistics(IECPRPLAN::LAN_B).missing, 1);
+        assert_eq!(discard.statistics(IECPRPLAN::LAN_B).out_of_order, 1);
+    }
+
+    #[test]
+    fn entries_age_out_after_400ms(){
+        let mut discard = DuplicateDiscard::default();
+        discard.on_frame(SOURCE, IECPRPLAN::LAN_A, 5, at(0));
+        assert!(!discard.on_frame(SOURCE, IECPRPLAN::LAN_B, 5, at(399)).is_accepted());
+
+        discard.on_frame(SOURCE, IECPRPLAN::LAN_A, 6, at(500));
+        assert!(discard.on_frame(SOURCE, IECPRPLAN::LAN_B, 6, at(900)).is_accepted());
+
+        // expire counts frames that never arrived on the other LAN without waiting for traffic
+        discard.on_frame(SOURCE, IECPRPLAN::LAN_A, 7, at(1000));
+        discard.expire(at(1399));
+        assert_eq!(discard.statistics(IECPRPLAN::LAN_B).missing, 1);
+        discard.expire(at(1400));
+        assert_eq!(discard.statistics(IECPRPLAN::LAN_B).missing, 2);
+    }
+
+    #[test]
+    fn statistics_per_lan(){
+        let mut discard = DuplicateDiscard::default();
+        for sequence in [1, 2, 3]{
+            discard.on_frame(SOURCE, IECPRPLAN::LAN_A, sequence, at(0));
+        }
+        for sequence in [2, 1]{
+            discard.on_frame(SOURCE, IECPRPLAN::LAN_B, sequence, at(1));
+        }
+        discard.expire(at(1000));
+
+        let lan_a = LANStatistics{ received: 3, duplicates: 0, missing: 0, out_of_order: 0 };
+        let lan_b = LANStatistics{ received: 2, duplicates: 2, missing: 1, out_of_order: 1 };
+        assert_eq!(discard.statistics(IECPRPLAN::LAN_A), &lan_a);
+        assert_eq!(discard.statistics(IECPRPLAN::LAN_B), &lan_b);
+    }
+}
@@ -0,0 +1,244 @@
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+// IEC 61850-90-5 routable GOOSE: the goosePdu wrapped in a session protocol data unit (SPDU)
+// and sent as a UDP datagram instead of an Ethernet frame
+
+use serde::{Serialize, Deserialize};
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::pdu_encoder::{encodeIECGoosePdu,sizeGoosePduFrame};
+use crate::pdu_decoder::decodeIECGoosePdu;
+use crate::basic_encoder::{encode_octet_string,size_length};
+use crate::basic_decoder::{check_bounds,decode_tag_length};
+
+pub const RGOOSE_PORT:u16 = 102;
+
+// session identifier of a non tunnelled GOOSE SPDU
+pub const RGOOSE_SESSION_ID:u8 = 0xa1;
+pub const RGOOSE_PAYLOAD_TYPE:u8 = 0x81;
+
+// SI, LI, common session header tag and length, then the 22 bytes of the common session header
+const SESSION_HEADER_SIZE:usize = 26;
+// payload length field
+const PAYLOAD_LENGTH_SIZE:usize = 4;
+// payload type, simulation, APPID and APDU length in front of the goosePdu
+const PAYLOAD_HEADER_SIZE:usize = 6;
+
+#[derive(Serialize,Deserialize,Debug,Default,Clone,PartialEq,Eq)]
+pub struct RGooseSecurityInfo {
+    pub timeOfCurrentKey: u32,
+    pub timeToNextKey: u16,
+    pub encryptionAlgorithm: u8,
+    pub signatureAlgorithm: u8,
+    pub keyID: u32,
+}
+
+#[derive(Serialize,Deserialize,Debug,Default,Clone)]
+pub struct IECRGoosePacket {
+    pub spduNumber: u32,
+    pub version: u16,
+    pub security: RGooseSecurityInfo,
+    pub simulation: bool,
+    pub APPID: [u8;2],
+    pub pdu: IECGoosePdu,
+    // empty without a signature algorithm, the 0x85 tag is sent either way
+    pub signature: Vec<u8>,
+}
+
+impl IECRGoosePacket {
+    // the same GOOSE message routed instead of sent on the local network
+    pub fn from_goose_packet(pkt: &IECGoosePacket, spduNumber: u32) -> IECRGoosePacket{
+        IECRGoosePacket{
+            spduNumber,
+            version: 1,
+            security: RGooseSecurityInfo::default(),
+            simulation: pkt.pdu.simulation,
+            APPID: pkt.goose_hdr.APPID,
+            pdu: pkt.pdu.clone(),
+            signature: vec![],
+        }
+    }
+}
+
+// exact number of bytes encodeRGoosePacket writes for this packet
+pub fn sizeRGoosePacket(pkt: &IECRGoosePacket) -> usize{
+    SESSION_HEADER_SIZE + PAYLOAD_LENGTH_SIZE + PAYLOAD_HEADER_SIZE + sizeGoosePduFrame(&pkt.pdu)
+        + 1 + size_length(pkt.signature.len()) + pkt.signature.len()
+}
+
+pub fn encodeRGoosePacket(pkt: &IECRGoosePacket, buffer: &mut[u8], pos:usize) -> Result<usize,GooseError>{
+    let spdu_size = sizeRGoosePacket(pkt);
+    if pos + spdu_size > buffer.len(){
+        return Err(GooseError{ message: format!("buffer too short, SPDU needs {} bytes", spdu_size), pos});
+    }
+    let apdu_size = sizeGoosePduFrame(&pkt.pdu);
+    if apdu_size > u16::MAX as usize{
+        return Err(GooseError{ message: format!("goosePdu too large ({} bytes)", apdu_size), pos});
+    }
+
+    let mut new_pos = pos;
+
+    buffer[new_pos] = RGOOSE_SESSION_ID;
+    buffer[new_pos+1] = (SESSION_HEADER_SIZE - 2) as u8;
+    buffer[new_pos+2] = 0x80; // common session header
+    buffer[new_pos+3] = (SESSION_HEADER_SIZE - 4) as u8;
+    new_pos += 4;
+
+    // SPDU length counts everything after itself up to the end of the signature
+    buffer[new_pos..new_pos+4].copy_from_slice(&((spdu_size - 8) as u32).to_be_bytes());
+    new_pos += 4;
+    buffer[new_pos..new_pos+4].copy_from_slice(&pkt.spduNumber.to_be_bytes());
+    new_pos += 4;
+    buffer[new_pos..new_pos+2].copy_from_slice(&pkt.version.to_be_bytes());
+    new_pos += 2;
+
+    buffer[new_pos..new_pos+4].copy_from_slice(&pkt.security.timeOfCurrentKey.to_be_bytes());
+    new_pos += 4;
+    buffer[new_pos..new_pos+2].copy_from_slice(&pkt.security.timeToNextKey.to_be_bytes());
+    new_pos += 2;
+    buffer[new_pos] = pkt.security.encryptionAlgorithm;
+    buffer[new_pos+1] = pkt.security.signatureAlgorithm;
+    new_pos += 2;
+    buffer[new_pos..new_pos+4].copy_from_slice(&pkt.security.keyID.to_be_bytes());
+    new_pos += 4;
+
+    buffer[new_pos..new_pos+4].copy_from_slice(&((PAYLOAD_HEADER_SIZE + apdu_size) as u32).to_be_bytes());
+    new_pos += 4;
+
+    buffer[new_pos] = RGOOSE_PAYLOAD_TYPE;
+    buffer[new_pos+1] = if pkt.simulation {0x01} else {0x00};
+    new_pos += 2;
+    buffer[new_pos..new_pos+2].copy_from_slice(&pkt.APPID);
+    new_pos += 2;
+    buffer[new_pos..new_pos+2].copy_from_slice(&(apdu_size as u16).to_be_bytes());
+    new_pos += 2;
+
+    new_pos = encodeIECGoosePdu(&pkt.pdu, buffer, new_pos)?;
+
+    new_pos = encode_octet_string(0x85, &pkt.signature, buffer, new_pos, true);
+
+    Ok(new_pos)
+}
+
+// None when the datagram is not a GOOSE SPDU, like decodeGoosePacket for non GOOSE frames
+pub fn decodeRGoosePacket(buffer: &[u8], pos:usize) -> Option<Result<IECRGoosePacket,GooseError>>{
+    if buffer.get(pos) != Some(&RGOOSE_SESSION_ID){
+        return None;
+    }
+    Some(decodeRGooseSpdu(buffer, pos))
+}
+
+fn decodeRGooseSpdu(buffer: &[u8], pos:usize) -> Result<IECRGoosePacket,GooseError>{
+    check_bounds(buffer, pos, SESSION_HEADER_SIZE + PAYLOAD_LENGTH_SIZE)?;
+
+    let mut new_pos = pos + 1;
+    if buffer[new_pos] as usize != SESSION_HEADER_SIZE - 2 || buffer[new_pos+1] != 0x80 || buffer[new_pos+2] as usize != SESSION_HEADER_SIZE - 4{
+        return Err(GooseError{ message: "unexpected R-GOOSE session header".into(), pos: new_pos});
+    }
+    new_pos += 3;
+
+    let spdu_length = u32::from_be_bytes(buffer[new_pos..new_pos+4].try_into().unwrap()) as usize;
+    new_pos += 4;
+    if check_bounds(buffer, new_pos, spdu_length).is_err() || spdu_length < SESSION_HEADER_SIZE - 8 + PAYLOAD_LENGTH_SIZE{
+        return Err(GooseError{ message: format!("SPDU length {} does not fit the datagram", spdu_length), pos: new_pos - 4});
+    }
+    // nothing may run past the end of the SPDU
+    let buffer = &buffer[..new_pos + spdu_length];
+
+    let mut pkt = IECRGoosePacket{
+        spduNumber: u32::from_be_bytes(buffer[new_pos..new_pos+4].try_into().unwrap()),
+        version: u16::from_be_bytes(buffer[new_pos+4..new_pos+6].try_into().unwrap()),
+        ..Default::default()
+    };
+    new_pos += 6;
+
+    pkt.security = RGooseSecurityInfo{
+        timeOfCurrentKey: u32::from_be_bytes(buffer[new_pos..new_pos+4].try_into().unwrap()),
+        timeToNextKey: u16::from_be_bytes(buffer[new_pos+4..new_pos+6].try_into().unwrap()),
+        encryptionAlgorithm: buffer[new_pos+6],
+        signatureAlgorithm: buffer[new_pos+7],
+        keyID: u32::from_be_bytes(buffer[new_pos+8..new_pos+12].try_into().unwrap()),
+    };
+    new_pos += 12;
+
+    let payload_length = u32::from_be_bytes(buffer[new_pos..new_pos+4].try_into().unwrap()) as usize;
+    new_pos += 4;
+    if check_bounds(buffer, new_pos, payload_length).is_err() || payload_length < PAYLOAD_HEADER_SIZE{
+        return Err(GooseError{ message: format!("payload length {} does not fit the SPDU", payload_length), pos: new_pos - 4});
+    }
+    let payload_end = new_pos + payload_length;
+
+    if buffer[new_pos] != RGOOSE_PAYLOAD_TYPE{
+        return Err(GooseError{ message: format!("unsupported R-GOOSE payload type 0x{:02x}", buffer[new_pos]), pos: new_pos});
+    }
+    pkt.simulation = buffer[new_pos+1] != 0;
+    pkt.APPID = [buffer[new_pos+2], buffer[new_pos+3]];
+    let apdu_length = u16::from_be_bytes(buffer[new_pos+4..new_pos+6].try_into().unwrap()) as usize;
+    new_pos += PAYLOAD_HEADER_SIZE;
+    if new_pos + apdu_length != payload_end{
+        return Err(GooseError{ message: format!("APDU length {} does not match payload length {}", apdu_length, payload_length), pos: new_pos - 2});
+    }
+
+    pkt.pdu = decodeIECGoosePdu(&buffer[..payload_end], &mut new_pos)?;
+    if new_pos != payload_end{
+        return Err(GooseError{ message: "goosePdu shorter than APDU length".into(), pos: new_pos});
+    }
+
+    let mut tag:u8 = 0;
+    let mut length:usize = 0;
+    new_pos = decode_tag_length(&mut tag, &mut length, buffer, new_pos)?;
+    if tag != 0x85{
+        return Err(GooseError{ message: format!("unexpected signature tag 0x{:02x}", tag), pos: new_pos});
+    }
+    pkt.signature = buffer[new_pos..new_pos+length].to_vec();
+    new_pos += length;
+
+    if new_pos != buffer.len(){
+        return Err(GooseError{ message: "trailing bytes after signature".into(), pos: new_pos});
+    }
+
+    Ok(pkt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    #[test]
+    fn rgoose_over_loopback_udp(){
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut pdu = IECGoosePdu{ gocbRef: "IED1LD0/LLN0$GO$gcb1".into(), goID: "gcb1".into(), stNum: 3, sqNum: 9, confRev: 2, ..Default::default() };
+        pdu.allData = vec![IECData::boolean(true), IECData::int32u(70000), IECData::visible_string("abc".into())];
+        pdu.numDatSetEntries = 3;
+        let pkt = IECRGoosePacket{
+            spduNumber: 42,
+            version: 1,
+            security: RGooseSecurityInfo{ timeOfCurrentKey: 1000, timeToNextKey: 60, encryptionAlgorithm: 0, signatureAlgorithm: 0, keyID: 7 },
+            simulation: false,
+            APPID: [0x10, 0x01],
+            pdu,
+            signature: vec![],
+        };
+        let mut buffer = vec![0_u8;sizeRGoosePacket(&pkt)];
+        let size = encodeRGoosePacket(&pkt, &mut buffer, 0).unwrap();
+        sender.send_to(&buffer[..size], receiver.local_addr().unwrap()).unwrap();
+
+        let mut datagram = [0_u8;1500];
+        let (received, from) = receiver.recv_from(&mut datagram).unwrap();
+        assert_eq!(from, sender.local_addr().unwrap());
+        let decoded = decodeRGoosePacket(&datagram[..received], 0).unwrap().unwrap();
+
+        assert_eq!((decoded.spduNumber, decoded.version, decoded.simulation, decoded.APPID), (42, 1, false, [0x10, 0x01]));
+        assert_eq!(decoded.security, pkt.security);
+        assert_eq!((decoded.pdu.gocbRef.as_str(), decoded.pdu.goID.as_str()), ("IED1LD0/LLN0$GO$gcb1", "gcb1"));
+        assert_eq!((decoded.pdu.stNum, decoded.pdu.sqNum, decoded.pdu.confRev, decoded.pdu.numDatSetEntries), (3, 9, 2, 3));
+        assert!(matches!(decoded.pdu.allData.as_slice(), [IECData::boolean(true), IECData::int32u(70000), IECData::visible_string(s)] if s == "abc"));
+    }
+}
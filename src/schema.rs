@@ -0,0 +1,314 @@
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+// expected layout of a dataset: a type tree mirroring IECData, used to reject frames whose
+// allData drifted from configuration and to build default valued allData for encoding
+
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+use crate::error::GooseError;
+use crate::types::{*};
+
+#[derive(Debug,Serialize,Deserialize,Clone,PartialEq,Eq)]
+pub enum IECDataSchema{
+    array(Vec<IECDataSchema>),
+    structure(Vec<IECDataSchema>),
+    boolean,
+
+    int8,
+    int16,
+    int32,
+    int64,
+
+    int8u,
+    int16u,
+    int32u,
+    int64u,
+
+    float32,
+    float64,
+
+    visible_string,
+    mms_string,
+    // number of significant bits, quality is 13, Dbpos 2
+    bit_string(usize),
+    octet_string,
+    utc_time,
+}
+
+impl IECDataSchema {
+    // schema of an existing value, e.g. a template from the SCL importer
+    pub fn from_data(data: &IECData) -> IECDataSchema{
+        match data{
+            IECData::array(val) => IECDataSchema::array(val.iter().map(IECDataSchema::from_data).collect()),
+            IECData::structure(val) => IECDataSchema::structure(val.iter().map(IECDataSchema::from_data).collect()),
+            IECData::boolean(_) => IECDataSchema::boolean,
+            IECData::int8(_) => IECDataSchema::int8,
+            IECData::int16(_) => IECDataSchema::int16,
+            IECData::int32(_) => IECDataSchema::int32,
+            IECData::int64(_) => IECDataSchema::int64,
+            IECData::int8u(_) => IECDataSchema::int8u,
+            IECData::int16u(_) => IECDataSchema::int16u,
+            IECData::int32u(_) => IECDataSchema::int32u,
+            IECData::int64u(_) => IECDataSchema::int64u,
+            IECData::float32(_) => IECDataSchema::float32,
+            IECData::float64(_) => IECDataSchema::float64,
+            IECData::visible_string(_) => IECDataSchema::visible_string,
+            IECData::mms_string(_) => IECDataSchema::mms_string,
+            IECData::bit_string{ padding, val } => IECDataSchema::bit_string(bitCount(*padding, val)),
+            IECData::octet_string(_) => IECDataSchema::octet_string,
+            IECData::utc_time(_) => IECDataSchema::utc_time,
+        }
+    }
+
+    // zero, false, empty or all bits cleared
+    pub fn default_data(&self) -> IECData{
+        match self{
+            IECDataSchema::array(val) => IECData::array(val.iter().map(IECDataSchema::default_data).collect()),
+            IECDataSchema::structure(val) => IECData::structure(val.iter().map(IECDataSchema::default_data).collect()),
+            IECDataSchema::boolean => IECData::boolean(false),
+            IECDataSchema::int8 => IECData::int8(0),
+            IECDataSchema::int16 => IECData::int16(0),
+            IECDataSchema::int32 => IECData::int32(0),
+            IECDataSchema::int64 => IECData::int64(0),
+            IECDataSchema::int8u => IECData::int8u(0),
+            IECDataSchema::int16u => IECData::int16u(0),
+            IECDataSchema::int32u => IECData::int32u(0),
+            IECDataSchema::int64u => IECData::int64u(0),
+            IECDataSchema::float32 => IECData::float32(0.0),
+            IECDataSchema::float64 => IECData::float64(0.0),
+            IECDataSchema::visible_string => IECData::visible_string(String::new()),
+            IECDataSchema::mms_string => IECData::mms_string(String::new()),
+            IECDataSchema::bit_string(bits) => {
+                let octets = bits.div_ceil(8);
+                IECData::bit_string{ padding: (octets * 8 - bits) as u8, val: vec![0x00;octets] }
+            },
+            IECDataSchema::octet_string => IECData::octet_string(vec![]),
+            IECDataSchema::utc_time => IECData::utc_time(UtcTime::default()),
+        }
+    }
+
+    // every integer at its widest type of the same signedness, for values whose declared width
+    // is unknown because they were decoded
+    pub fn widened(&self) -> IECDataSchema{
+        match self{
+            IECDataSchema::array(val) => IECDataSchema::array(val.iter().map(IECDataSchema::widened).collect()),
+            IECDataSchema::structure(val) => IECDataSchema::structure(val.iter().map(IECDataSchema::widened).collect()),
+            IECDataSchema::int8 | IECDataSchema::int16 | IECDataSchema::int32 => IECDataSchema::int64,
+            IECDataSchema::int8u | IECDataSchema::int16u | IECDataSchema::int32u => IECDataSchema::int64u,
+            other => other.clone(),
+        }
+    }
+
+    // data in the integer type of the schema, for a value validated against it
+    pub(crate) fn cast(&self, data: IECData) -> IECData{
+        let value = match integerValue(&data){
+            Some((_, value)) => value,
+            None => return data,
+        };
+        match self{
+            IECDataSchema::int8 => IECData::int8(value as i8),
+            IECDataSchema::int16 => IECData::int16(value as i16),
+            IECDataSchema::int32 => IECData::int32(value as i32),
+            IECDataSchema::int64 => IECData::int64(value as i64),
+            IECDataSchema::int8u => IECData::int8u(value as u8),
+            IECDataSchema::int16u => IECData::int16u(value as u16),
+            IECDataSchema::int32u => IECData::int32u(value as u32),
+            IECDataSchema::int64u => IECData::int64u(value as u64),
+            _ => data,
+        }
+    }
+
+    // signedness and bounds of the integer types
+    fn integer_range(&self) -> Option<(bool, i128, i128)>{
+        match self{
+            IECDataSchema::int8 => Some((true, i8::MIN as i128, i8::MAX as i128)),
+            IECDataSchema::int16 => Some((true, i16::MIN as i128, i16::MAX as i128)),
+            IECDataSchema::int32 => Some((true, i32::MIN as i128, i32::MAX as i128)),
+            IECDataSchema::int64 => Some((true, i64::MIN as i128, i64::MAX as i128)),
+            IECDataSchema::int8u => Some((false, 0, u8::MAX as i128)),
+            IECDataSchema::int16u => Some((false, 0, u16::MAX as i128)),
+            IECDataSchema::int32u => Some((false, 0, u32::MAX as i128)),
+            IECDataSchema::int64u => Some((false, 0, u64::MAX as i128)),
+            _ => None,
+        }
+    }
+
+    // path is the location of data used in the message, allData[3].structure[1]. the decoder picks
+    // the integer variant from the encoded length, int32u(5) comes back as int8u, so integers only
+    // have to match signedness and fit the declared width
+    pub fn validate(&self, data: &IECData, path: &str) -> Result<(),GooseError>{
+        let members = match (self, data){
+            (IECDataSchema::array(schema), IECData::array(val)) => Some(("array", schema, val)),
+            (IECDataSchema::structure(schema), IECData::structure(val)) => Some(("structure", schema, val)),
+            (IECDataSchema::bit_string(bits), IECData::bit_string{ padding, val }) => {
+                let actual = bitCount(*padding, val);
+                if actual != *bits{
+                    return Err(mismatch(path, &self.to_string(), &format!("bit_string({})", actual)));
+                }
+                None
+            },
+            (schema, data) => {
+                let actual = IECDataSchema::from_data(data);
+                if let (Some((signed, min, max)), Some((value_signed, value))) = (schema.integer_range(), integerValue(data)){
+                    if signed != value_signed{
+                        return Err(mismatch(path, &schema.to_string(), &actual.to_string()));
+                    }
+                    if value < min || value > max{
+                        return Err(GooseError{ message: format!("{}: {} does not fit {}", path, value, schema), pos: 0 });
+                    }
+                    return Ok(());
+                }
+                if std::mem::discriminant(schema) != std::mem::discriminant(&actual){
+                    return Err(mismatch(path, &schema.to_string(), &actual.to_string()));
+                }
+                None
+            },
+        };
+
+        if let Some((kind, schema, val)) = members{
+            if schema.len() != val.len(){
+                return Err(GooseError{ message: format!("{}: expected {} with {} elements, got {}", path, kind, schema.len(), val.len()), pos: 0 });
+            }
+            for (i, (schema, data)) in schema.iter().zip(val.iter()).enumerate(){
+                schema.validate(data, &format!("{}.{}[{}]", path, kind, i))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// signedness and value of an integer
+fn integerValue(data: &IECData) -> Option<(bool, i128)>{
+    match data{
+        IECData::int8(val) => Some((true, *val as i128)),
+        IECData::int16(val) => Some((true, *val as i128)),
+        IECData::int32(val) => Some((true, *val as i128)),
+        IECData::int64(val) => Some((true, *val as i128)),
+        IECData::int8u(val) => Some((false, *val as i128)),
+        IECData::int16u(val) => Some((false, *val as i128)),
+        IECData::int32u(val) => Some((false, *val as i128)),
+        IECData::int64u(val) => Some((false, *val as i128)),
+        _ => None,
+    }
+}
+
+fn bitCount(padding: u8, val: &[u8]) -> usize{
+    (val.len() * 8).saturating_sub(padding as usize)
+}
+
+fn mismatch(path: &str, expected: &str, actual: &str) -> GooseError{
+    GooseError{ message: format!("{}: expected {}, got {}", path, expected, actual), pos: 0 }
+}
+
+impl fmt::Display for IECDataSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let (kind, members) = match self{
+            IECDataSchema::array(val) => ("array", val),
+            IECDataSchema::structure(val) => ("structure", val),
+            IECDataSchema::bit_string(bits) => return write!(f, "bit_string({})", bits),
+            other => return write!(f, "{:?}", other),
+        };
+        write!(f, "{}{{", kind)?;
+        for (i, member) in members.iter().enumerate(){
+            if i > 0{
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", member)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+// expected allData of a control block
+#[derive(Debug,Serialize,Deserialize,Clone,PartialEq,Eq,Default)]
+pub struct DataSetSchema {
+    pub members: Vec<IECDataSchema>,
+}
+
+impl DataSetSchema {
+    pub fn new(members: Vec<IECDataSchema>) -> DataSetSchema{
+        DataSetSchema{ members }
+    }
+
+    pub fn from_data(allData: &[IECData]) -> DataSetSchema{
+        DataSetSchema{ members: allData.iter().map(IECDataSchema::from_data).collect() }
+    }
+
+    pub fn default_data(&self) -> Vec<IECData>{
+        self.members.iter().map(IECDataSchema::default_data).collect()
+    }
+
+    // schema of the value at path, the allData index followed by the structure or array indices
+    pub fn member(&self, path: &[usize]) -> Option<&IECDataSchema>{
+        let (index, nested) = path.split_first()?;
+        let mut schema = self.members.get(*index)?;
+        for index in nested{
+            schema = match schema{
+                IECDataSchema::array(val) | IECDataSchema::structure(val) => val.get(*index)?,
+                _ => return None,
+            };
+        }
+        Some(schema)
+    }
+
+    pub fn validate_data(&self, allData: &[IECData]) -> Result<(),GooseError>{
+        if allData.len() != self.members.len(){
+            return Err(GooseError{ message: format!("allData: expected {} entries, got {}", self.members.len(), allData.len()), pos: 0 });
+        }
+        for (i, (schema, data)) in self.members.iter().zip(allData.iter()).enumerate(){
+            schema.validate(data, &format!("allData[{}]", i))?;
+        }
+        Ok(())
+    }
+
+    // first mismatch between pdu.allData and the schema, with its path
+    pub fn validate(&self, pdu: &IECGoosePdu) -> Result<(),GooseError>{
+        if pdu.numDatSetEntries as usize != self.members.len(){
+            return Err(GooseError{ message: format!("numDatSetEntries: expected {}, got {}", self.members.len(), pdu.numDatSetEntries), pos: 0 });
+        }
+        self.validate_data(&pdu.allData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu_encoder::encodeIECDataElement;
+    use crate::pdu_decoder::decodeIECDataElement;
+
+    fn roundTrip(data: &IECData) -> IECData{
+        let mut buffer = [0_u8;64];
+        let end = encodeIECDataElement(data, &mut buffer, 0);
+        let (pos, decoded) = decodeIECDataElement(&buffer[..end], 0).unwrap();
+        assert_eq!(pos, end);
+        decoded
+    }
+
+    #[test]
+    fn integers_validate_after_decoding(){
+        let allData = vec![
+            IECData::int32u(5),
+            IECData::int32(7),
+            IECData::int64u(10),
+            IECData::int16(-300),
+            IECData::structure(vec![IECData::int64(-1), IECData::int32u(70000)]),
+        ];
+        let schema = DataSetSchema::from_data(&allData);
+        let decoded: Vec<IECData> = allData.iter().map(roundTrip).collect();
+        assert!(matches!(decoded[0], IECData::int8u(5)));
+        schema.validate_data(&decoded).unwrap();
+    }
+
+    #[test]
+    fn integers_keep_signedness_and_width(){
+        let e = IECDataSchema::int32u.validate(&IECData::int8(5), "allData[0]").unwrap_err();
+        assert_eq!(e.message, "allData[0]: expected int32u, got int8");
+        let e = IECDataSchema::int8.validate(&IECData::int32(300), "allData[1]").unwrap_err();
+        assert_eq!(e.message, "allData[1]: 300 does not fit int8");
+        IECDataSchema::int8u.validate(&IECData::int64u(255), "allData[2]").unwrap();
+        IECDataSchema::boolean.validate(&IECData::int8(1), "allData[3]").unwrap_err();
+    }
+}
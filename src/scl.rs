@@ -0,0 +1,466 @@
+#![allow(non_snake_case)]
+
+// IEC 61850-6 SCL import: GSEControl blocks of a CID/SCD file become GOOSE packet templates,
+// the GSE address gives MAC, APPID, VLAN and the retransmission times, the dataset FCDAs are
+// resolved against DataTypeTemplates into default valued allData
+
+use std::path::Path;
+use std::time::Duration;
+
+use roxmltree::{Document, Node};
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::publisher::{GooseClock,GooseSink,GoosePublisher,RetransmissionCurve};
+use crate::subscriber::GooseSubscriber;
+use crate::schema::DataSetSchema;
+use crate::dataset::DataSetBinding;
+
+#[derive(Debug,Clone)]
+pub struct SclGooseControl {
+    pub iedName: String,
+    pub ldInst: String,
+    pub cbName: String,
+    // dataset members in allData order, e.g. LD0/XCBR1.Pos.stVal[ST]
+    pub fcdas: Vec<String>,
+    // fcdas and the attributes nested in them, LD0/TripPTRC1.Tr.general[ST]
+    pub binding: DataSetBinding,
+    pub curve: RetransmissionCurve,
+    // srcAddr is not part of SCL and left zero
+    pub packet: IECGoosePacket,
+}
+
+impl SclGooseControl {
+    pub fn publisher<C: GooseClock, S: GooseSink>(&self, clock: C, sink: S) -> GoosePublisher<C, S>{
+        GoosePublisher::new(self.packet.clone(), self.curve.clone(), clock, sink)
+    }
+
+    pub fn schema(&self) -> DataSetSchema{
+        DataSetSchema::from_data(&self.packet.pdu.allData)
+    }
+
+    pub fn subscribe(&self, subscriber: &mut GooseSubscriber){
+        subscriber.expect_confRev(&self.packet.pdu.gocbRef, self.packet.pdu.confRev);
+        subscriber.expect_schema(&self.packet.pdu.gocbRef, self.schema());
+    }
+}
+
+pub fn loadScl<P: AsRef<Path>>(path: P) -> Result<Vec<SclGooseControl>,GooseError>{
+    let xml = std::fs::read_to_string(path.as_ref()).map_err(|e| GooseError{ message: format!("cannot read {}: {}", path.as_ref().display(), e), pos: 0 })?;
+    parseScl(&xml)
+}
+
+// every GOOSE control block of every IED in the file, positions in errors are byte offsets into xml
+pub fn parseScl(xml: &str) -> Result<Vec<SclGooseControl>,GooseError>{
+    let doc = Document::parse(xml).map_err(|e| GooseError{ message: format!("invalid SCL: {}", e), pos: 0 })?;
+    let scl = doc.root_element();
+    let templates = children(scl, "DataTypeTemplates").next();
+
+    let mut controls = vec![];
+    for ied in children(scl, "IED"){
+        let iedName = attribute(ied, "name")?;
+        for ldevice in ied.descendants().filter(|node| node.has_tag_name("LDevice")){
+            let ldInst = attribute(ldevice, "inst")?;
+            let ldName = ldevice.attribute("ldName").map_or_else(|| format!("{}{}", iedName, ldInst), str::to_string);
+            let Some(ln0) = children(ldevice, "LN0").next() else {
+                continue;
+            };
+            for control in children(ln0, "GSEControl"){
+                if control.attribute("type").is_some_and(|kind| kind != "GOOSE"){
+                    continue;
+                }
+                let builder = SclBuilder{ scl, templates, ied, ldevice };
+                controls.push(builder.goose_control(control, iedName, ldInst, &ldName)?);
+            }
+        }
+    }
+    Ok(controls)
+}
+
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, tag: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a{
+    node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+fn attribute<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str,GooseError>{
+    node.attribute(name).ok_or_else(|| GooseError{ message: format!("{} without {} attribute", node.tag_name().name(), name), pos: node.range().start })
+}
+
+fn sclError(node: Node, message: String) -> GooseError{
+    GooseError{ message, pos: node.range().start }
+}
+
+struct SclBuilder<'a, 'input> {
+    scl: Node<'a, 'input>,
+    templates: Option<Node<'a, 'input>>,
+    ied: Node<'a, 'input>,
+    ldevice: Node<'a, 'input>,
+}
+
+impl<'a, 'input> SclBuilder<'a, 'input> {
+    fn goose_control(&self, control: Node, iedName: &str, ldInst: &str, ldName: &str) -> Result<SclGooseControl,GooseError>{
+        let cbName = attribute(control, "name")?;
+        let gocbRef = format!("{}/LLN0$GO${}", ldName, cbName);
+
+        let mut fcdas = vec![];
+        let mut allData = vec![];
+        let mut members = vec![];
+        let mut binding = DataSetBinding::default();
+        let datSet = match control.attribute("datSet"){
+            Some(name) => {
+                let ln0 = children(self.ldevice, "LN0").next()
+                    .ok_or_else(|| sclError(control, format!("{}: logical device without LN0", gocbRef)))?;
+                let dataset = children(ln0, "DataSet").find(|node| node.attribute("name") == Some(name))
+                    .ok_or_else(|| sclError(control, format!("{}: dataset {} not found", gocbRef, name)))?;
+                for (index, fcda) in children(dataset, "FCDA").enumerate(){
+                    let (reference, data) = self.fcda(fcda, index, &mut members)?;
+                    binding.bind(&reference, vec![index]);
+                    fcdas.push(reference);
+                    allData.push(data);
+                }
+                for (reference, path) in members{
+                    binding.bind(&reference, path);
+                }
+                format!("{}/LLN0${}", ldName, name)
+            },
+            None => String::new(),
+        };
+
+        let gse = self.gse(iedName, ldInst, cbName).ok_or_else(|| sclError(control, format!("{}: no GSE address in Communication", gocbRef)))?;
+        let mut eth_hdr = EthernetHeader::default();
+        let mut goose_hdr = IECGooseHeader::default();
+        let mut vlan = None;
+        if let Some(address) = children(gse, "Address").next(){
+            for p in children(address, "P"){
+                let text = p.text().unwrap_or("").trim();
+                let invalid = || sclError(p, format!("{}: invalid {} {:?}", gocbRef, p.attribute("type").unwrap_or(""), text));
+                match p.attribute("type"){
+                    Some("MAC-Address") => {
+                        let octets: Vec<u8> = text.split('-').map(|octet| u8::from_str_radix(octet, 16)).collect::<Result<_,_>>().map_err(|_| invalid())?;
+                        eth_hdr.dstAddr = octets.try_into().map_err(|_| invalid())?;
+                    },
+                    Some("APPID") => {
+                        goose_hdr.APPID = u16::from_str_radix(text, 16).map_err(|_| invalid())?.to_be_bytes();
+                    },
+                    Some("VLAN-ID") => {
+                        let id = u16::from_str_radix(text, 16).ok().filter(|id| *id <= 0x0fff).ok_or_else(invalid)?;
+                        vlan.get_or_insert_with(VLANTag::default).id = id;
+                    },
+                    Some("VLAN-PRIORITY") => {
+                        let priority = text.parse::<u8>().ok().filter(|priority| *priority <= 7).ok_or_else(invalid)?;
+                        vlan.get_or_insert_with(VLANTag::default).priority = priority;
+                    },
+                    _ => {},
+                }
+            }
+        }
+        eth_hdr.VLAN = vlan;
+
+        let mut curve = RetransmissionCurve::default();
+        if let Some(min_time) = children(gse, "MinTime").next(){
+            curve.min_time = duration(min_time)?;
+        }
+        if let Some(max_time) = children(gse, "MaxTime").next(){
+            curve.max_time = duration(max_time)?;
+        }
+
+        let confRev = match control.attribute("confRev"){
+            Some(text) => text.parse().map_err(|_| sclError(control, format!("{}: invalid confRev {:?}", gocbRef, text)))?,
+            None => 0,
+        };
+
+        binding.declare(DataSetSchema::from_data(&allData));
+
+        let pdu = IECGoosePdu{
+            gocbRef: gocbRef.clone(),
+            timeAllowedtoLive: curve.time_allowed_to_live(),
+            datSet,
+            goID: control.attribute("appID").map_or_else(|| gocbRef.clone(), str::to_string),
+            confRev,
+            numDatSetEntries: allData.len() as u32,
+            allData,
+            ..Default::default()
+        };
+
+        Ok(SclGooseControl{
+            iedName: iedName.to_string(),
+            ldInst: ldInst.to_string(),
+            cbName: cbName.to_string(),
+            fcdas,
+            binding,
+            curve,
+            packet: IECGoosePacket{ eth_hdr, goose_hdr, pdu, prp: None },
+        })
+    }
+
+    fn gse(&self, iedName: &str, ldInst: &str, cbName: &str) -> Option<Node<'a, 'input>>{
+        let communication = children(self.scl, "Communication").next()?;
+        communication.descendants()
+            .filter(|node| node.has_tag_name("ConnectedAP") && node.attribute("iedName") == Some(iedName))
+            .flat_map(|ap| children(ap, "GSE"))
+            .find(|gse| gse.attribute("ldInst") == Some(ldInst) && gse.attribute("cbName") == Some(cbName))
+    }
+
+    // reference and default value of one dataset member, members collects the references of
+    // the attributes nested in it
+    fn fcda(&self, fcda: Node, index: usize, members: &mut Vec<(String, Vec<usize>)>) -> Result<(String, IECData),GooseError>{
+        let ldInst = attribute(fcda, "ldInst")?;
+        let prefix = fcda.attribute("prefix").unwrap_or("");
+        let lnClass = attribute(fcda, "lnClass")?;
+        let lnInst = fcda.attribute("lnInst").unwrap_or("");
+        let doName = attribute(fcda, "doName")?;
+        let daName = fcda.attribute("daName").filter(|name| !name.is_empty());
+        let fc = attribute(fcda, "fc")?;
+
+        let mut name = format!("{}/{}{}{}.{}", ldInst, prefix, lnClass, lnInst, doName);
+        if let Some(daName) = daName{
+            name = format!("{}.{}", name, daName);
+        }
+        let reference = format!("{}[{}]", name, fc);
+        let error = |message: String| sclError(fcda, format!("{}: {}", reference, message));
+
+        let ldevice = self.ied.descendants().find(|node| node.has_tag_name("LDevice") && node.attribute("inst") == Some(ldInst))
+            .ok_or_else(|| error(format!("logical device {} not found", ldInst)))?;
+        let ln = ldevice.children().find(|node| {
+            let tag = node.tag_name().name();
+            (tag == "LN0" || tag == "LN") && node.attribute("lnClass") == Some(lnClass)
+                && node.attribute("prefix").unwrap_or("") == prefix && node.attribute("inst").unwrap_or("") == lnInst
+        }).ok_or_else(|| error("logical node not found".into()))?;
+
+        let lnType = self.template("LNodeType", attribute(ln, "lnType")?).ok_or_else(|| error("LNodeType not found".into()))?;
+
+        // doName may address sub data objects, A.phsA
+        let mut names = doName.split('.');
+        let first = names.next().unwrap_or("");
+        let mut data_object = children(lnType, "DO").find(|node| node.attribute("name") == Some(first))
+            .ok_or_else(|| error(format!("data object {} not found", first)))?;
+        for name in names{
+            let doType = self.template("DOType", attribute(data_object, "type")?).ok_or_else(|| error("DOType not found".into()))?;
+            data_object = children(doType, "SDO").find(|node| node.attribute("name") == Some(name))
+                .ok_or_else(|| error(format!("sub data object {} not found", name)))?;
+        }
+        let doType = self.template("DOType", attribute(data_object, "type")?).ok_or_else(|| error("DOType not found".into()))?;
+
+        let mut nested = vec![];
+        let data = match daName{
+            None => IECData::structure(self.data_object(doType, fc, &name, &[index], &mut nested)?),
+            Some(daName) => {
+                // daName may address components of a constructed attribute, origin.orCat
+                let mut names = daName.split('.');
+                let first = names.next().unwrap_or("");
+                let mut attribute_node = children(doType, "DA").find(|node| node.attribute("name") == Some(first) && node.attribute("fc") == Some(fc))
+                    .ok_or_else(|| error(format!("data attribute {} with fc {} not found", first, fc)))?;
+                for name in names{
+                    let daType = self.template("DAType", attribute(attribute_node, "type")?).ok_or_else(|| error("DAType not found".into()))?;
+                    attribute_node = children(daType, "BDA").find(|node| node.attribute("name") == Some(name))
+                        .ok_or_else(|| error(format!("attribute component {} not found", name)))?;
+                }
+                self.data_attribute(attribute_node, &name, &[index], &mut nested)?
+            },
+        };
+        members.extend(nested.into_iter().map(|(name, path)| (format!("{}[{}]", name, fc), path)));
+        Ok((reference, data))
+    }
+
+    fn template(&self, tag: &str, id: &str) -> Option<Node<'a, 'input>>{
+        self.templates?.children().find(|node| node.has_tag_name(tag) && node.attribute("id") == Some(id))
+    }
+
+    // attributes of a data object with the functional constraint fc, in type order
+    fn data_object(&self, doType: Node, fc: &str, name: &str, path: &[usize], members: &mut Vec<(String, Vec<usize>)>) -> Result<Vec<IECData>,GooseError>{
+        let mut data = vec![];
+        for child in doType.children().filter(Node::is_element){
+            let member_name = format!("{}.{}", name, attribute(child, "name")?);
+            let member_path = [path, &[data.len()]].concat();
+            match child.tag_name().name(){
+                "DA" if child.attribute("fc") == Some(fc) => {
+                    members.push((member_name.clone(), member_path.clone()));
+                    data.push(self.data_attribute(child, &member_name, &member_path, members)?);
+                },
+                "SDO" => {
+                    let sdoType = self.template("DOType", attribute(child, "type")?)
+                        .ok_or_else(|| sclError(child, format!("DOType {} not found", child.attribute("type").unwrap_or(""))))?;
+                    let mut nested = vec![];
+                    let sdo = self.data_object(sdoType, fc, &member_name, &member_path, &mut nested)?;
+                    if !sdo.is_empty(){
+                        members.push((member_name, member_path));
+                        members.extend(nested);
+                        data.push(IECData::structure(sdo));
+                    }
+                },
+                _ => {},
+            }
+        }
+        Ok(data)
+    }
+
+    // DA or BDA
+    fn data_attribute(&self, node: Node, name: &str, path: &[usize], members: &mut Vec<(String, Vec<usize>)>) -> Result<IECData,GooseError>{
+        let bType = attribute(node, "bType")?;
+        let data = if bType == "Struct"{
+            let daType = self.template("DAType", attribute(node, "type")?)
+                .ok_or_else(|| sclError(node, format!("DAType {} not found", node.attribute("type").unwrap_or(""))))?;
+            let mut data = vec![];
+            for bda in children(daType, "BDA"){
+                let member_name = format!("{}.{}", name, attribute(bda, "name")?);
+                let member_path = [path, &[data.len()]].concat();
+                members.push((member_name.clone(), member_path.clone()));
+                data.push(self.data_attribute(bda, &member_name, &member_path, members)?);
+            }
+            IECData::structure(data)
+        }
+        else{
+            defaultData(bType).ok_or_else(|| sclError(node, format!("unsupported bType {}", bType)))?
+        };
+        // arrays of basic or constructed types, array elements are not named
+        match node.attribute("count").map(str::parse::<usize>){
+            Some(Ok(count)) if count > 0 => {
+                members.retain(|(_, member)| !member.starts_with(path) || member.len() == path.len());
+                Ok(IECData::array(vec![data;count]))
+            },
+            Some(Ok(_)) | None => Ok(data),
+            Some(Err(_)) => Err(sclError(node, format!("invalid count {:?}", node.attribute("count").unwrap_or("")))),
+        }
+    }
+}
+
+// IEC 61850-8-1 mapping of the SCL basic types
+fn defaultData(bType: &str) -> Option<IECData>{
+    let data = match bType{
+        "BOOLEAN" => IECData::boolean(false),
+        "INT8" => IECData::int8(0),
+        "INT16" => IECData::int16(0),
+        "INT32" => IECData::int32(0),
+        "INT64" => IECData::int64(0),
+        "INT8U" => IECData::int8u(0),
+        "INT16U" => IECData::int16u(0),
+        "INT24U" | "INT32U" => IECData::int32u(0),
+        "FLOAT32" => IECData::float32(0.0),
+        "FLOAT64" => IECData::float64(0.0),
+        "Enum" => IECData::int8(0),
+        "Dbpos" | "Tcmd" | "Check" => IECData::bit_string{ padding: 6, val: vec![0x00] },
+        "Quality" => IECData::bit_string{ padding: 3, val: vec![0x00, 0x00] },
+        "Timestamp" | "EntryTime" => IECData::utc_time(UtcTime::default()),
+        "Octet64" => IECData::octet_string(vec![]),
+        "VisString32" | "VisString64" | "VisString65" | "VisString129" | "VisString255" | "ObjRef" | "Currency" => IECData::visible_string(String::new()),
+        "Unicode255" => IECData::mms_string(String::new()),
+        _ => return None,
+    };
+    Some(data)
+}
+
+fn duration(node: Node) -> Result<Duration,GooseError>{
+    let text = node.text().unwrap_or("").trim();
+    let value: f64 = text.parse().ok().filter(|value: &f64| value.is_finite() && *value >= 0.0)
+        .ok_or_else(|| sclError(node, format!("invalid {} {:?}", node.tag_name().name(), text)))?;
+    // the unit is s, the schema default multiplier m when the attribute is left out. an empty
+    // multiplier is no SI prefix, so seconds
+    let scale = match node.attribute("multiplier").unwrap_or("m"){
+        "" => 1.0,
+        "m" => 1e-3,
+        "u" => 1e-6,
+        multiplier => return Err(sclError(node, format!("unsupported multiplier {:?}", multiplier))),
+    };
+    Duration::try_from_secs_f64(value * scale).map_err(|_| sclError(node, format!("{} {:?} out of range", node.tag_name().name(), text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_CID: &str = include_str!("scl_sample.cid");
+
+    fn parse_error(from: &str, to: &str) -> GooseError{
+        let xml = SAMPLE_CID.replacen(from, to, 1);
+        assert_ne!(xml, SAMPLE_CID, "{} not in the sample", from);
+        parseScl(&xml).unwrap_err()
+    }
+
+    #[test]
+    fn sample_cid(){
+        let controls = parseScl(SAMPLE_CID).unwrap();
+        assert_eq!(controls.len(), 1);
+        let control = &controls[0];
+        let pkt = &control.packet;
+        assert_eq!(pkt.pdu.gocbRef, "IED1LD0/LLN0$GO$gcb1");
+        assert_eq!(pkt.pdu.datSet, "IED1LD0/LLN0$ds1");
+        assert_eq!(pkt.pdu.goID, "IED1_gcb1");
+        assert_eq!((pkt.pdu.confRev, pkt.pdu.numDatSetEntries, pkt.pdu.timeAllowedtoLive), (3, 4, 2000));
+        assert_eq!(pkt.eth_hdr.dstAddr, [0x01, 0x0c, 0xcd, 0x01, 0x00, 0x01]);
+        assert_eq!(pkt.goose_hdr.APPID, [0x30, 0x01]);
+        let vlan = pkt.eth_hdr.VLAN.as_ref().unwrap();
+        assert_eq!((vlan.id, vlan.priority), (0x00a, 4));
+        assert_eq!((control.curve.min_time, control.curve.max_time), (Duration::from_millis(4), Duration::from_millis(1000)));
+
+        assert_eq!(control.fcdas, vec!["LD0/XCBR1.Pos.stVal[ST]", "LD0/XCBR1.Pos.q[ST]", "LD0/XCBR1.Pos.t[ST]", "LD0/TripPTRC1.Tr[ST]"]);
+        let allData = &pkt.pdu.allData;
+        assert!(matches!(allData[0], IECData::bit_string{ padding: 6, .. }));
+        assert!(matches!(allData[1], IECData::bit_string{ padding: 3, .. }));
+        assert!(matches!(allData[2], IECData::utc_time(_)));
+        // the whole data object, attributes with fc ST in type order
+        assert!(matches!(&allData[3], IECData::structure(members) if members.len() == 4));
+        assert!(matches!(control.binding.get(allData, "LD0/TripPTRC1.Tr.phsA[ST]"), Some(IECData::boolean(false))));
+        assert_eq!(control.binding.path("LD0/TripPTRC1.Tr.q[ST]"), Some(&[3, 2][..]));
+    }
+
+    #[test]
+    fn binding_keeps_declared_types(){
+        let xml = SAMPLE_CID.replace(r#"<DA name="phsA" bType="BOOLEAN" fc="ST"/>"#, r#"<DA name="phsA" bType="INT32" fc="ST"/>"#);
+        let control = parseScl(&xml).unwrap().remove(0);
+        let mut allData = control.packet.pdu.allData.clone();
+        control.binding.set(&mut allData, "TripPTRC1.Tr.phsA[ST]", IECData::int64(7)).unwrap();
+        assert!(matches!(control.binding.get(&allData, "TripPTRC1.Tr.phsA[ST]"), Some(IECData::int32(7))));
+        let e = control.binding.set(&mut allData, "TripPTRC1.Tr.phsA[ST]", IECData::int64(1 << 40)).unwrap_err();
+        assert_eq!(e.message, "TripPTRC1.Tr.phsA[ST]: 1099511627776 does not fit int32");
+    }
+
+    #[test]
+    fn durations(){
+        let controls = parseScl(&SAMPLE_CID.replace(r#"multiplier="m">1000"#, r#"multiplier="">2"#)).unwrap();
+        assert_eq!(controls[0].curve.max_time, Duration::from_secs(2));
+        let controls = parseScl(&SAMPLE_CID.replace(r#"<MinTime unit="s" multiplier="m">4"#, r#"<MinTime>8"#)).unwrap();
+        assert_eq!(controls[0].curve.min_time, Duration::from_millis(8));
+
+        let e = parse_error(r#"multiplier="m">1000"#, r#"multiplier="m">1e30"#);
+        assert!(e.message.contains("MaxTime \"1e30\" out of range"), "{}", e.message);
+        assert!(parse_error(r#"multiplier="m">4"#, r#"multiplier="k">4"#).message.contains("unsupported multiplier"));
+        assert!(parse_error(r#"multiplier="m">4"#, r#"multiplier="m">-4"#).message.contains("invalid MinTime"));
+    }
+
+    #[test]
+    fn missing_dataset(){
+        let e = parse_error(r#"datSet="ds1""#, r#"datSet="ds2""#);
+        assert_eq!(e.message, "IED1LD0/LLN0$GO$gcb1: dataset ds2 not found");
+        assert_eq!(&SAMPLE_CID.replacen(r#"datSet="ds1""#, r#"datSet="ds2""#, 1)[e.pos..e.pos + 11], "<GSEControl");
+    }
+
+    #[test]
+    fn unknown_bType(){
+        let e = parse_error(r#"bType="Dbpos""#, r#"bType="Dbpos2""#);
+        assert_eq!(e.message, "unsupported bType Dbpos2");
+    }
+
+    #[test]
+    fn unknown_logical_node(){
+        let e = parse_error(r#"<LN lnClass="XCBR" inst="1""#, r#"<LN lnClass="XCBR" inst="2""#);
+        assert_eq!(e.message, "LD0/XCBR1.Pos.stVal[ST]: logical node not found");
+    }
+
+    #[test]
+    fn array_attribute(){
+        let xml = SAMPLE_CID.replace(r#"<DA name="phsA" bType="BOOLEAN" fc="ST"/>"#, r#"<DA name="phsA" bType="BOOLEAN" count="3" fc="ST"/>"#);
+        let controls = parseScl(&xml).unwrap();
+        let control = &controls[0];
+        let phsA = control.binding.get(&control.packet.pdu.allData, "LD0/TripPTRC1.Tr.phsA[ST]").unwrap();
+        assert!(matches!(phsA, IECData::array(elements) if elements.len() == 3));
+
+        let e = parse_error(r#"<DA name="phsA" bType="BOOLEAN" fc="ST"/>"#, r#"<DA name="phsA" bType="BOOLEAN" count="x" fc="ST"/>"#);
+        assert_eq!(e.message, "invalid count \"x\"");
+    }
+
+    #[test]
+    fn missing_ln0(){
+        let xml = SAMPLE_CID.replace("<LN0 ", "<LNX ").replace("</LN0>", "</LNX>");
+        // control blocks live in LN0, without it there is nothing to import
+        assert!(parseScl(&xml).unwrap().is_empty());
+    }
+}
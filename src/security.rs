@@ -0,0 +1,242 @@
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+// IEC 62351-6 GOOSE authentication: reserved 1 carries the length of an extension behind
+// the goosePdu holding an HMAC-SHA256 over APPID up to the end of the goosePdu
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::error::{GooseError,GooseSecurityError};
+use crate::types::{*};
+use crate::pdu::{encodeGooseFrame,sizeFrame,decodeGooseFrame,simulationHeader,GOOSE_ETHER_TYPE};
+use crate::pdu_encoder::sizeGoosePduFrame;
+use crate::stream_encoder::{GooseSizeTree,fillIECGoosePduToSlice};
+use crate::basic_encoder::{encode_tag_length,size_length};
+use crate::basic_decoder::decode_tag_length;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const AUTHENTICATION_TAG:u8 = 0xaf;
+
+// the profiles truncate the HMAC to the leftmost bytes
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum GooseMacAlgorithm {
+    HmacSha256_80,
+    HmacSha256_128,
+    HmacSha256_256,
+}
+
+impl GooseMacAlgorithm {
+    pub fn mac_length(&self) -> usize{
+        match self{
+            GooseMacAlgorithm::HmacSha256_80 => 10,
+            GooseMacAlgorithm::HmacSha256_128 => 16,
+            GooseMacAlgorithm::HmacSha256_256 => 32,
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct GooseKey {
+    pub algorithm: GooseMacAlgorithm,
+    pub key: Vec<u8>,
+}
+
+// looks up the key of a received frame, typically by APPID or gocbRef
+pub trait GooseKeyProvider {
+    fn key(&self, pkt: &IECGoosePacket) -> Option<GooseKey>;
+}
+
+impl<F> GooseKeyProvider for F where F: Fn(&IECGoosePacket) -> Option<GooseKey> {
+    fn key(&self, pkt: &IECGoosePacket) -> Option<GooseKey>{
+        self(pkt)
+    }
+}
+
+fn computeMac(key: &GooseKey, data: &[u8]) -> HmacSha256{
+    let mut mac = HmacSha256::new_from_slice(&key.key).expect("HMAC takes keys of any size");
+    mac.update(data);
+    mac
+}
+
+// number of bytes announced in reserved 1
+pub fn sizeAuthenticationExtension(key: &GooseKey) -> usize{
+    let mac_length = key.algorithm.mac_length();
+    1 + size_length(mac_length) + mac_length
+}
+
+// signs buffer[start..end] and writes the extension at end
+pub(crate) fn encodeAuthenticationExtension(key: &GooseKey, buffer: &mut[u8], start: usize, end: usize) -> usize{
+    let mac_length = key.algorithm.mac_length();
+    let value = computeMac(key, &buffer[start..end]).finalize().into_bytes();
+
+    let new_pos = encode_tag_length(AUTHENTICATION_TAG, mac_length, buffer, end, true);
+    buffer[new_pos..new_pos+mac_length].copy_from_slice(&value[..mac_length]);
+    new_pos + mac_length
+}
+
+// exact number of bytes encodeSecureGoosePacket writes for this packet
+pub fn sizeSecureGoosePacket(pkt: &IECGoosePacket, key: &GooseKey) -> usize{
+    sizeFrame(&pkt.eth_hdr, &pkt.prp, sizeGoosePduFrame(&pkt.pdu), sizeAuthenticationExtension(key))
+}
+
+pub fn encodeSecureGoosePacket(pkt: &IECGoosePacket, key: &GooseKey, buffer: &mut[u8], pos:usize) -> Result<usize,GooseError>{
+    let goose_hdr = simulationHeader(pkt)?;
+    let tree = GooseSizeTree::new(&pkt.pdu);
+    let frame_size = sizeFrame(&pkt.eth_hdr, &pkt.prp, tree.frame_size(), sizeAuthenticationExtension(key));
+    if pos + frame_size > buffer.len(){
+        return Err(GooseError{ message: format!("buffer too short, frame needs {} bytes", frame_size), pos});
+    }
+    encodeGooseFrame(&pkt.eth_hdr, &goose_hdr, GOOSE_ETHER_TYPE, &pkt.prp, Some(key), buffer, pos, |buffer, pos| fillIECGoosePduToSlice(&pkt.pdu, &tree, buffer, pos))
+}
+
+// like decodeGoosePacket but only returns frames carrying a valid authentication value
+pub fn decodeSecureGoosePacket<K: GooseKeyProvider>(buffer: &[u8], pos:usize, keys: &K) -> Option<Result<IECGoosePacket,GooseSecurityError>>{
+    match decodeGooseFrame(buffer, pos)?{
+        Ok((pkt, layout)) => {
+            if layout.extension_length == 0{
+                return Some(Err(GooseSecurityError::MissingAuthentication));
+            }
+            let key = match keys.key(&pkt){
+                Some(key) => key,
+                None => {return Some(Err(GooseSecurityError::UnknownKey));}
+            };
+            let extension = &buffer[layout.pdu_end..layout.pdu_end + layout.extension_length];
+            match verifyAuthenticationExtension(&key, &buffer[layout.appid_pos..layout.pdu_end], extension, layout.pdu_end){
+                Ok(()) => Some(Ok(pkt)),
+                Err(e) => Some(Err(e)),
+            }
+        },
+        Err(e) => Some(Err(GooseSecurityError::Malformed(e))),
+    }
+}
+
+fn verifyAuthenticationExtension(key: &GooseKey, signed: &[u8], extension: &[u8], pos: usize) -> Result<(),GooseSecurityError>{
+    let mut tag:u8 = 0;
+    let mut length:usize = 0;
+    let value_pos = decode_tag_length(&mut tag, &mut length, extension, 0).map_err(|e| GooseError{ message: e.message, pos: pos + e.pos })?;
+    if tag != AUTHENTICATION_TAG || value_pos + length != extension.len(){
+        return Err(GooseError{ message: format!("unexpected security extension tag 0x{:02x}", tag), pos}.into());
+    }
+    if length != key.algorithm.mac_length(){
+        return Err(GooseSecurityError::AuthenticationFailed);
+    }
+
+    computeMac(key, signed).verify_truncated_left(&extension[value_pos..]).map_err(|_| GooseSecurityError::AuthenticationFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::{encodeGoosePacket,sizeGoosePacket,sizeEthernetHeader};
+
+    fn packet() -> IECGoosePacket{
+        let mut pkt = IECGoosePacket::default();
+        pkt.pdu.gocbRef = "IED1LD0/LLN0$GO$gcb1".into();
+        pkt.pdu.allData = vec![IECData::boolean(true), IECData::int8(-5)];
+        pkt.pdu.numDatSetEntries = 2;
+        pkt
+    }
+
+    fn key(algorithm: GooseMacAlgorithm) -> GooseKey{
+        GooseKey{ algorithm, key: b"0123456789abcdef0123456789abcdef".to_vec() }
+    }
+
+    fn encode(pkt: &IECGoosePacket, key: &GooseKey) -> Vec<u8>{
+        let mut buffer = vec![0_u8;sizeSecureGoosePacket(pkt, key)];
+        let size = encodeSecureGoosePacket(pkt, key, &mut buffer, 0).unwrap();
+        assert_eq!(size, buffer.len());
+        buffer
+    }
+
+    fn decode(buffer: &[u8], key: &GooseKey) -> Result<IECGoosePacket,GooseSecurityError>{
+        decodeSecureGoosePacket(buffer, 0, &|_: &IECGoosePacket| Some(key.clone())).unwrap()
+    }
+
+    // reserved 1 holds the extension length
+    fn reserved1_pos(pkt: &IECGoosePacket) -> usize{
+        sizeEthernetHeader(&pkt.eth_hdr) + 2 + 4
+    }
+
+    #[test]
+    fn round_trip(){
+        let pkt = packet();
+        for algorithm in [GooseMacAlgorithm::HmacSha256_80, GooseMacAlgorithm::HmacSha256_128, GooseMacAlgorithm::HmacSha256_256]{
+            let key = key(algorithm);
+            let buffer = encode(&pkt, &key);
+            let pos = reserved1_pos(&pkt);
+            assert_eq!(u16::from_be_bytes([buffer[pos], buffer[pos + 1]]) as usize, sizeAuthenticationExtension(&key));
+            assert_eq!(&buffer[buffer.len() - algorithm.mac_length() - 2..][..2], &[AUTHENTICATION_TAG, algorithm.mac_length() as u8]);
+
+            let decoded = decode(&buffer, &key).unwrap();
+            assert_eq!(decoded.pdu.gocbRef, pkt.pdu.gocbRef);
+            assert!(matches!(decoded.pdu.allData[1], IECData::int8(-5)));
+        }
+    }
+
+    #[test]
+    fn tampered_frame(){
+        let pkt = packet();
+        let key = key(GooseMacAlgorithm::HmacSha256_128);
+        let mut buffer = encode(&pkt, &key);
+        let gocbRef = buffer.windows(4).position(|window| window == b"gcb1").unwrap();
+        buffer[gocbRef + 3] = b'2';
+        assert!(matches!(decode(&buffer, &key), Err(GooseSecurityError::AuthenticationFailed)));
+    }
+
+    #[test]
+    fn wrong_key(){
+        let pkt = packet();
+        let buffer = encode(&pkt, &key(GooseMacAlgorithm::HmacSha256_128));
+        let mut other = key(GooseMacAlgorithm::HmacSha256_128);
+        other.key[0] ^= 0x01;
+        assert!(matches!(decode(&buffer, &other), Err(GooseSecurityError::AuthenticationFailed)));
+        // a key of another profile expects another MAC length
+        assert!(matches!(decode(&buffer, &key(GooseMacAlgorithm::HmacSha256_256)), Err(GooseSecurityError::AuthenticationFailed)));
+    }
+
+    #[test]
+    fn plain_frame_and_unknown_key(){
+        let pkt = packet();
+        let mut plain = vec![0_u8;sizeGoosePacket(&pkt)];
+        encodeGoosePacket(&pkt, &mut plain, 0).unwrap();
+        let key = key(GooseMacAlgorithm::HmacSha256_80);
+        assert!(matches!(decode(&plain, &key), Err(GooseSecurityError::MissingAuthentication)));
+
+        let buffer = encode(&pkt, &key);
+        let result = decodeSecureGoosePacket(&buffer, 0, &|_: &IECGoosePacket| None).unwrap();
+        assert!(matches!(result, Err(GooseSecurityError::UnknownKey)));
+    }
+
+    #[test]
+    fn malformed_extension(){
+        let pkt = packet();
+        let key = key(GooseMacAlgorithm::HmacSha256_128);
+        let buffer = encode(&pkt, &key);
+        let pos = reserved1_pos(&pkt);
+        let size = sizeAuthenticationExtension(&key) as u16;
+
+        // reserved 1 announces one byte less than the extension holds
+        let mut truncated = buffer.clone();
+        truncated[pos..pos + 2].copy_from_slice(&(size - 1).to_be_bytes());
+        assert!(matches!(decode(&truncated, &key), Err(GooseSecurityError::Malformed(_))));
+
+        // one byte more than the extension holds
+        let mut overlong = buffer.clone();
+        overlong[pos..pos + 2].copy_from_slice(&(size + 1).to_be_bytes());
+        overlong.push(0x00);
+        assert!(matches!(decode(&overlong, &key), Err(GooseSecurityError::Malformed(_))));
+
+        // more than the frame holds
+        let mut beyond = buffer.clone();
+        beyond[pos..pos + 2].copy_from_slice(&0x0400_u16.to_be_bytes());
+        assert!(matches!(decode(&beyond, &key), Err(GooseSecurityError::Malformed(_))));
+
+        // a wrong tag
+        let mut tag = buffer.clone();
+        let extension = tag.len() - size as usize;
+        tag[extension] = 0xae;
+        assert!(matches!(decode(&tag, &key), Err(GooseSecurityError::Malformed(_))));
+    }
+}
@@ -0,0 +1,270 @@
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+// single pass goosePdu encoder: the lengths of nested arrays and structures are computed once
+// into a size tree, then the pdu is written front to back into any std::io::Write
+// (Vec<u8>, &mut [u8], a file or a socket). every primitive goes out in one write_all, but the
+// headers of arrays and structures are writes of their own, wrap files and sockets in a BufWriter
+
+use std::io::Write;
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::basic_encoder::{*};
+use crate::pdu_encoder::sizeIECDataElement;
+
+// content lengths of the constructed elements of allData in pre-order
+#[derive(Debug,Default,Clone)]
+pub struct GooseSizeTree {
+    sizes: Vec<usize>,
+    goosePduLength: usize,
+    dataSetSize: usize,
+}
+
+impl GooseSizeTree {
+    pub fn new(pdu: & IECGoosePdu) -> GooseSizeTree{
+        let mut tree = GooseSizeTree::default();
+        tree.update(pdu);
+        tree
+    }
+
+    // recomputes the sizes for pdu, reusing the allocation
+    pub fn update(&mut self, pdu: & IECGoosePdu){
+        self.sizes.clear();
+        self.dataSetSize = pdu.allData.iter().map(|data| sizeNode(data, &mut self.sizes)).sum();
+
+        let fill = false;
+        let mut length = 0;
+        let mut add = |size: usize| length += 1 + size_length(size) + size;
+        add(encode_string(0x80, &pdu.gocbRef, &mut [], 0, fill));
+        add(encode_unsigned(0x81, pdu.timeAllowedtoLive, &mut [], 0, fill));
+        add(encode_string(0x82, &pdu.datSet, &mut [], 0, fill));
+        add(encode_string(0x83, &pdu.goID, &mut [], 0, fill));
+        add(encode_utc_time(0x84, &pdu.t, &mut [], 0, fill));
+        add(encode_unsigned(0x85, pdu.stNum, &mut [], 0, fill));
+        add(encode_unsigned(0x86, pdu.sqNum, &mut [], 0, fill));
+        add(encode_boolean(0x87, pdu.simulation, &mut [], 0, fill));
+        add(encode_unsigned(0x88, pdu.confRev, &mut [], 0, fill));
+        add(encode_boolean(0x89, pdu.ndsCom, &mut [], 0, fill));
+        add(encode_unsigned(0x8a, pdu.numDatSetEntries, &mut [], 0, fill));
+        add(self.dataSetSize);
+        self.goosePduLength = length;
+    }
+
+    // total number of bytes written, including the 0x61 tag and length
+    pub fn frame_size(&self) -> usize{
+        1 + size_length(self.goosePduLength) + self.goosePduLength
+    }
+}
+
+// encoded size of data, pushing the content length of every array/structure on the way
+fn sizeNode(data: & IECData, sizes: &mut Vec<usize>) -> usize{
+    match data{
+        IECData::array(val) | IECData::structure(val) => {
+            let slot = sizes.len();
+            sizes.push(0);
+            let content: usize = val.iter().map(|element| sizeNode(element, sizes)).sum();
+            sizes[slot] = content;
+            1 + size_length(content) + content
+        },
+        // leaves are sized without recursion
+        _ => sizeIECDataElement(data, &mut []),
+    }
+}
+
+fn writeBytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(),GooseError>{
+    writer.write_all(bytes).map_err(|e| GooseError{ message: format!("write failed: {}", e), pos: 0 })
+}
+
+// short elements are built in a scratch buffer with the basic encoders and written in one go
+fn writeScratch<W: Write, F>(writer: &mut W, encode: F) -> Result<usize,GooseError>
+    where F: FnOnce(&mut [u8]) -> usize{
+    let mut scratch = [0_u8;16];
+    let size = encode(&mut scratch);
+    writeBytes(writer, &scratch[..size])?;
+    Ok(size)
+}
+
+// strings, octet strings and bit strings up to this size are written together with their tag and length
+const PRIMITIVE_SCRATCH_SIZE:usize = 256;
+
+fn writeTagLength<W: Write>(writer: &mut W, tag: u8, length: usize) -> Result<usize,GooseError>{
+    writeScratch(writer, |scratch| encode_tag_length(tag, length, scratch, 0, true))
+}
+
+fn writeOctets<W: Write>(writer: &mut W, tag: u8, value: &[u8]) -> Result<usize,GooseError>{
+    writePrimitive(writer, tag, value.len(), value.iter().copied())
+}
+
+// tag, length and the value octets assembled on the stack, longer values go out in
+// PRIMITIVE_SCRATCH_SIZE chunks
+fn writePrimitive<W: Write, I>(writer: &mut W, tag: u8, length: usize, value: I) -> Result<usize,GooseError>
+    where I: Iterator<Item = u8>{
+    let mut scratch = [0_u8;PRIMITIVE_SCRATCH_SIZE];
+    let mut used = encode_tag_length(tag, length, &mut scratch, 0, true);
+    let size = used + length;
+    for byte in value{
+        if used == PRIMITIVE_SCRATCH_SIZE{
+            writeBytes(writer, &scratch)?;
+            used = 0;
+        }
+        scratch[used] = byte;
+        used += 1;
+    }
+    writeBytes(writer, &scratch[..used])?;
+    Ok(size)
+}
+
+fn writeIECDataElement<W: Write>(writer: &mut W, data: & IECData, sizes: &mut std::slice::Iter<usize>) -> Result<usize,GooseError>{
+    let fill = true;
+    match data{
+        IECData::boolean(val) => writeScratch(writer, |scratch| encode_boolean(0x83, *val, scratch, 0, fill)),
+
+        IECData::int8(val) => writeScratch(writer, |scratch| encode_interger_general(0x85, &val.to_be_bytes(), scratch, 0, fill)),
+        IECData::int16(val) => writeScratch(writer, |scratch| encode_interger_general(0x85, &val.to_be_bytes(), scratch, 0, fill)),
+        IECData::int32(val) => writeScratch(writer, |scratch| encode_interger_general(0x85, &val.to_be_bytes(), scratch, 0, fill)),
+        IECData::int64(val) => writeScratch(writer, |scratch| encode_interger_general(0x85, &val.to_be_bytes(), scratch, 0, fill)),
+
+        IECData::int8u(val) => writeScratch(writer, |scratch| encode_unsigned_general(0x86, &val.to_be_bytes(), scratch, 0, fill)),
+        IECData::int16u(val) => writeScratch(writer, |scratch| encode_unsigned_general(0x86, &val.to_be_bytes(), scratch, 0, fill)),
+        IECData::int32u(val) => writeScratch(writer, |scratch| encode_unsigned_general(0x86, &val.to_be_bytes(), scratch, 0, fill)),
+        IECData::int64u(val) => writeScratch(writer, |scratch| encode_unsigned_general(0x86, &val.to_be_bytes(), scratch, 0, fill)),
+
+        IECData::float32(val) => writeScratch(writer, |scratch| encode_float_general(0x87, &val.to_be_bytes(), scratch, 0, fill)),
+        IECData::float64(val) => writeScratch(writer, |scratch| encode_float_general(0x87, &val.to_be_bytes(), scratch, 0, fill)),
+
+        IECData::visible_string(val) => writeOctets(writer, 0x8a, val.as_bytes()),
+        IECData::mms_string(val) => writeOctets(writer, 0x90, val.as_bytes()),
+        IECData::bit_string{ padding, val } => {
+            let octets = val.iter().rev().map(|byte| byte.reverse_bits());
+            writePrimitive(writer, 0x84, val.len() + 1, std::iter::once(*padding).chain(octets))
+        },
+        IECData::array(val) | IECData::structure(val) => {
+            let tag = if matches!(data, IECData::array(_)) {0xa1} else {0xa2};
+            let content = *sizes.next().ok_or_else(|| GooseError{ message: "size tree does not match the pdu".into(), pos: 0 })?;
+            let mut size = writeTagLength(writer, tag, content)?;
+            for element in val.iter(){
+                size += writeIECDataElement(writer, element, sizes)?;
+            }
+            if size != 1 + size_length(content) + content{
+                return Err(GooseError{ message: "size tree does not match the pdu".into(), pos: 0 });
+            }
+            Ok(size)
+        },
+        IECData::octet_string(val) => writeOctets(writer, 0x89, val),
+        IECData::utc_time(val) => writeScratch(writer, |scratch| encode_utc_time(0x91, val, scratch, 0, fill)),
+    }
+}
+
+// writes pdu using the sizes in tree, which has to be built from the same pdu; returns the bytes written
+pub fn writeIECGoosePdu<W: Write>(pdu: & IECGoosePdu, tree: & GooseSizeTree, writer: &mut W) -> Result<usize,GooseError>{
+    let fill = true;
+    let mut size = writeTagLength(writer, 0x61, tree.goosePduLength)?;
+
+    size += writeOctets(writer, 0x80, pdu.gocbRef.as_bytes())?;
+    size += writeScratch(writer, |scratch| encode_unsigned(0x81, pdu.timeAllowedtoLive, scratch, 0, fill))?;
+    size += writeOctets(writer, 0x82, pdu.datSet.as_bytes())?;
+    size += writeOctets(writer, 0x83, pdu.goID.as_bytes())?;
+    size += writeScratch(writer, |scratch| encode_utc_time(0x84, &pdu.t, scratch, 0, fill))?;
+    size += writeScratch(writer, |scratch| encode_unsigned(0x85, pdu.stNum, scratch, 0, fill))?;
+    size += writeScratch(writer, |scratch| encode_unsigned(0x86, pdu.sqNum, scratch, 0, fill))?;
+    size += writeScratch(writer, |scratch| encode_boolean(0x87, pdu.simulation, scratch, 0, fill))?;
+    size += writeScratch(writer, |scratch| encode_unsigned(0x88, pdu.confRev, scratch, 0, fill))?;
+    size += writeScratch(writer, |scratch| encode_boolean(0x89, pdu.ndsCom, scratch, 0, fill))?;
+    size += writeScratch(writer, |scratch| encode_unsigned(0x8a, pdu.numDatSetEntries, scratch, 0, fill))?;
+
+    size += writeTagLength(writer, 0xab, tree.dataSetSize)?;
+    let mut sizes = tree.sizes.iter();
+    for data in pdu.allData.iter(){
+        size += writeIECDataElement(writer, data, &mut sizes)?;
+    }
+
+    if size != tree.frame_size(){
+        return Err(GooseError{ message: "size tree does not match the pdu".into(), pos: 0 });
+    }
+    Ok(size)
+}
+
+// same bytes as encodeIECGoosePdu, appended to buffer
+pub fn encodeIECGoosePduToVec(pdu: & IECGoosePdu, buffer: &mut Vec<u8>) -> Result<usize,GooseError>{
+    let tree = GooseSizeTree::new(pdu);
+    buffer.reserve(tree.frame_size());
+    writeIECGoosePdu(pdu, &tree, buffer)
+}
+
+// drop-in for encodeIECGoosePdu, returns the position after the pdu
+pub fn encodeIECGoosePduToSlice(pdu: & IECGoosePdu, buffer: &mut[u8], pos:usize) -> Result<usize,GooseError>{
+    fillIECGoosePduToSlice(pdu, &GooseSizeTree::new(pdu), buffer, pos)
+}
+
+// encodeIECGoosePduToSlice with a tree the caller already built to size the frame
+pub(crate) fn fillIECGoosePduToSlice(pdu: & IECGoosePdu, tree: & GooseSizeTree, buffer: &mut[u8], pos:usize) -> Result<usize,GooseError>{
+    let required = tree.frame_size();
+    if pos + required > buffer.len(){
+        return Err(GooseError{ message: format!("buffer too short, goosePdu needs {} bytes", required), pos});
+    }
+    let mut writer = &mut buffer[pos..];
+    Ok(pos + writeIECGoosePdu(pdu, tree, &mut writer)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu_encoder::{encodeIECGoosePdu,sizeGoosePduFrame};
+
+    // counts the write calls reaching an unbuffered sink
+    #[derive(Default)]
+    struct CountingWriter {
+        bytes: Vec<u8>,
+        writes: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>{
+            self.writes += 1;
+            self.bytes.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()>{
+            Ok(())
+        }
+    }
+
+    fn pdu(allData: Vec<IECData>) -> IECGoosePdu{
+        IECGoosePdu{ gocbRef: "IED1LD0/LLN0$GO$gcb1".into(), numDatSetEntries: allData.len() as u32, allData, ..Default::default() }
+    }
+
+    #[test]
+    fn same_bytes_as_the_two_pass_encoder(){
+        let pdu = pdu(vec![
+            IECData::bit_string{ padding: 3, val: vec![0x01, 0x80] },
+            IECData::bit_string{ padding: 0, val: (0..=255).cycle().take(600).collect() },
+            IECData::visible_string("x".repeat(300)),
+            IECData::structure(vec![IECData::int32(-70000), IECData::array(vec![IECData::float64(0.5), IECData::utc_time(UtcTime::default())])]),
+        ]);
+        let mut reference = vec![0_u8;sizeGoosePduFrame(&pdu)];
+        encodeIECGoosePdu(&pdu, &mut reference, 0).unwrap();
+
+        let mut buffer = vec![];
+        assert_eq!(encodeIECGoosePduToVec(&pdu, &mut buffer).unwrap(), reference.len());
+        assert_eq!(buffer, reference);
+
+        let mut slice = vec![0_u8;reference.len() + 2];
+        assert_eq!(encodeIECGoosePduToSlice(&pdu, &mut slice, 2).unwrap(), slice.len());
+        assert_eq!(slice[2..], reference[..]);
+    }
+
+    #[test]
+    fn one_write_per_primitive(){
+        let pdu = pdu(vec![
+            IECData::bit_string{ padding: 3, val: vec![0x01, 0x80] },
+            IECData::visible_string("abc".into()),
+            IECData::structure(vec![IECData::boolean(true), IECData::int8u(5)]),
+        ]);
+        let mut writer = CountingWriter::default();
+        writeIECGoosePdu(&pdu, &GooseSizeTree::new(&pdu), &mut writer).unwrap();
+        // goosePdu header, eleven fields, allData header, two primitives, the structure header and its two members
+        assert_eq!(writer.writes, 1 + 11 + 1 + 2 + 1 + 2);
+    }
+}
@@ -0,0 +1,240 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::types::{*};
+use crate::schema::DataSetSchema;
+
+// a GOOSE stream is identified by its control block, APPID and publisher MAC
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub struct GooseStreamKey {
+    pub gocbRef: String,
+    pub APPID: [u8;2],
+    pub srcAddr: [u8;6],
+}
+
+impl GooseStreamKey {
+    pub fn from_packet(pkt: &IECGoosePacket) -> GooseStreamKey{
+        GooseStreamKey{ gocbRef: pkt.pdu.gocbRef.clone(), APPID: pkt.goose_hdr.APPID, srcAddr: pkt.eth_hdr.srcAddr }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum GooseEvent {
+    // first message of a stream (previous_stNum None) or a new stNum
+    StateChange{ key: GooseStreamKey, stNum: u32, previous_stNum: Option<u32>, rollover: bool },
+    // sqNum advanced by one within the same state
+    Retransmission{ key: GooseStreamKey, stNum: u32, sqNum: u32 },
+    // same stNum and sqNum as the previous message
+    Duplicate{ key: GooseStreamKey, stNum: u32, sqNum: u32 },
+    // sqNum went backwards or skipped messages, or stNum went backwards (stNum is then the stale
+    // one and expected_sqNum follows the current state)
+    OutOfOrder{ key: GooseStreamKey, stNum: u32, sqNum: u32, expected_sqNum: u32 },
+    // no message within timeAllowedtoLive of the last one
+    Timeout{ key: GooseStreamKey, last_received: SystemTime, timeAllowedtoLive: u32 },
+    ConfigMismatch{ key: GooseStreamKey, expected_confRev: u32, confRev: u32 },
+    NeedsCommissioning{ key: GooseStreamKey },
+    // reserved 1 and the goosePdu disagree on the simulation flag
+    SimulationMismatch{ key: GooseStreamKey, header_simulation: bool, pdu_simulation: bool },
+    // allData does not match the configured schema, the message is dropped
+    SchemaMismatch{ key: GooseStreamKey, message: String },
+}
+
+#[derive(Debug,Clone)]
+pub struct GooseStreamState {
+    pub stNum: u32,
+    pub sqNum: u32,
+    pub confRev: u32,
+    pub ndsCom: bool,
+    pub timeAllowedtoLive: u32,
+    pub last_received: SystemTime,
+    pub timed_out: bool,
+}
+
+impl GooseStreamState {
+    pub fn deadline(&self) -> SystemTime{
+        self.last_received + Duration::from_millis(self.timeAllowedtoLive as u64)
+    }
+}
+
+fn next_counter(value: u32) -> u32{
+    if value == u32::MAX { 1 } else { value + 1 }
+}
+
+#[derive(Debug,Default)]
+pub struct GooseSubscriber {
+    streams: HashMap<GooseStreamKey, GooseStreamState>,
+    expected_confRev: HashMap<String, u32>,
+    schemas: HashMap<String, DataSetSchema>,
+}
+
+impl GooseSubscriber {
+    pub fn new() -> GooseSubscriber{
+        GooseSubscriber::default()
+    }
+
+    // confRev from configuration, without it a change against the previous message is reported
+    pub fn expect_confRev(&mut self, gocbRef: &str, confRev: u32){
+        self.expected_confRev.insert(gocbRef.to_string(), confRev);
+    }
+
+    // messages of gocbRef whose allData does not match schema are rejected
+    pub fn expect_schema(&mut self, gocbRef: &str, schema: DataSetSchema){
+        self.schemas.insert(gocbRef.to_string(), schema);
+    }
+
+    pub fn stream(&self, key: &GooseStreamKey) -> Option<&GooseStreamState>{
+        self.streams.get(key)
+    }
+
+    pub fn streams(&self) -> impl Iterator<Item = (&GooseStreamKey, &GooseStreamState)>{
+        self.streams.iter()
+    }
+
+    pub fn on_packet(&mut self, pkt: &IECGoosePacket, now: SystemTime) -> Vec<GooseEvent>{
+        let key = GooseStreamKey::from_packet(pkt);
+        let pdu = &pkt.pdu;
+        let mut events = vec![];
+
+        if let Some(schema) = self.schemas.get(&pdu.gocbRef){
+            if let Err(e) = schema.validate(pdu){
+                return vec![GooseEvent::SchemaMismatch{ key, message: e.message }];
+            }
+        }
+
+        let previous = self.streams.get(&key);
+
+        let expected_confRev = self.expected_confRev.get(&pdu.gocbRef).copied().or(previous.map(|state| state.confRev));
+        if let Some(expected_confRev) = expected_confRev{
+            if expected_confRev != pdu.confRev{
+                events.push(GooseEvent::ConfigMismatch{ key: key.clone(), expected_confRev, confRev: pdu.confRev });
+            }
+        }
+
+        if pkt.goose_hdr.simulation != pdu.simulation{
+            events.push(GooseEvent::SimulationMismatch{ key: key.clone(), header_simulation: pkt.goose_hdr.simulation, pdu_simulation: pdu.simulation });
+        }
+
+        if pdu.ndsCom && !previous.is_some_and(|state| state.ndsCom){
+            events.push(GooseEvent::NeedsCommissioning{ key: key.clone() });
+        }
+
+        let mut sqNum = pdu.sqNum;
+        match previous{
+            None => {
+                events.push(GooseEvent::StateChange{ key: key.clone(), stNum: pdu.stNum, previous_stNum: None, rollover: false });
+            },
+            Some(state) if state.stNum == pdu.stNum => {
+                let expected_sqNum = next_counter(state.sqNum);
+                if pdu.sqNum == state.sqNum{
+                    events.push(GooseEvent::Duplicate{ key: key.clone(), stNum: pdu.stNum, sqNum: pdu.sqNum });
+                }
+                else if pdu.sqNum == expected_sqNum{
+                    events.push(GooseEvent::Retransmission{ key: key.clone(), stNum: pdu.stNum, sqNum: pdu.sqNum });
+                }
+                else{
+                    events.push(GooseEvent::OutOfOrder{ key: key.clone(), stNum: pdu.stNum, sqNum: pdu.sqNum, expected_sqNum });
+                    // a late message must not move the sequence backwards
+                    if pdu.sqNum < state.sqNum{
+                        sqNum = state.sqNum;
+                    }
+                }
+            },
+            // a late message of an earlier state, unless the stream timed out and the publisher restarted
+            Some(state) if pdu.stNum < state.stNum && !(state.stNum == u32::MAX && pdu.stNum == 1) && !state.timed_out => {
+                // the stream keeps its state, a delayed frame must not extend its liveness
+                events.push(GooseEvent::OutOfOrder{ key, stNum: pdu.stNum, sqNum: pdu.sqNum, expected_sqNum: next_counter(state.sqNum) });
+                return events;
+            },
+            Some(state) => {
+                let rollover = state.stNum == u32::MAX && pdu.stNum == next_counter(state.stNum);
+                events.push(GooseEvent::StateChange{ key: key.clone(), stNum: pdu.stNum, previous_stNum: Some(state.stNum), rollover });
+            },
+        }
+
+        self.streams.insert(key, GooseStreamState{
+            stNum: pdu.stNum,
+            sqNum,
+            confRev: pdu.confRev,
+            ndsCom: pdu.ndsCom,
+            timeAllowedtoLive: pdu.timeAllowedtoLive,
+            last_received: now,
+            timed_out: false,
+        });
+
+        events
+    }
+
+    // reports every stream whose timeAllowedtoLive expired since its last message, once
+    pub fn check_timeouts(&mut self, now: SystemTime) -> Vec<GooseEvent>{
+        let mut events = vec![];
+        for (key, state) in self.streams.iter_mut(){
+            if !state.timed_out && state.deadline() < now{
+                state.timed_out = true;
+                events.push(GooseEvent::Timeout{ key: key.clone(), last_received: state.last_received, timeAllowedtoLive: state.timeAllowedtoLive });
+            }
+        }
+        events
+    }
+
+    // earliest deadline of the streams still alive, to know how long to wait before check_timeouts
+    pub fn next_deadline(&self) -> Option<SystemTime>{
+        self.streams.values().filter(|state| !state.timed_out).map(|state| state.deadline()).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn packet(stNum: u32, sqNum: u32) -> IECGoosePacket{
+        let mut pkt = IECGoosePacket::default();
+        pkt.pdu.gocbRef = "IED1LD0/LLN0$GO$gcb1".into();
+        pkt.pdu.stNum = stNum;
+        pkt.pdu.sqNum = sqNum;
+        pkt.pdu.timeAllowedtoLive = 2000;
+        pkt
+    }
+
+    fn at(ms: u64) -> SystemTime{
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_000 + ms)
+    }
+
+    #[test]
+    fn stale_stNum_is_out_of_order(){
+        let mut subscriber = GooseSubscriber::new();
+        let key = GooseStreamKey::from_packet(&packet(0, 0));
+        subscriber.on_packet(&packet(5, 0), at(0));
+        subscriber.on_packet(&packet(5, 1), at(1));
+
+        let mut stale = packet(4, 3);
+        stale.pdu.timeAllowedtoLive = 60000;
+        stale.pdu.ndsCom = true;
+        let events = subscriber.on_packet(&stale, at(2));
+        assert_eq!(events, vec![
+            GooseEvent::NeedsCommissioning{ key: key.clone() },
+            GooseEvent::OutOfOrder{ key: key.clone(), stNum: 4, sqNum: 3, expected_sqNum: 2 },
+        ]);
+        // the stale message does not move the stream back or keep it alive
+        let state = subscriber.stream(&key).unwrap();
+        assert_eq!((state.stNum, state.sqNum, state.ndsCom), (5, 1, false));
+        assert_eq!((state.last_received, state.timeAllowedtoLive), (at(1), 2000));
+        assert_eq!(subscriber.next_deadline(), Some(at(2001)));
+        assert_eq!(subscriber.on_packet(&packet(5, 2), at(3)), vec![GooseEvent::Retransmission{ key: key.clone(), stNum: 5, sqNum: 2 }]);
+    }
+
+    #[test]
+    fn stNum_rollover_and_restart(){
+        let mut subscriber = GooseSubscriber::new();
+        let key = GooseStreamKey::from_packet(&packet(0, 0));
+        subscriber.on_packet(&packet(u32::MAX, 4), at(0));
+        assert_eq!(subscriber.on_packet(&packet(1, 0), at(1)), vec![GooseEvent::StateChange{ key: key.clone(), stNum: 1, previous_stNum: Some(u32::MAX), rollover: true }]);
+
+        // after a timeout a lower stNum is a restarted publisher
+        subscriber.on_packet(&packet(7, 0), at(2));
+        assert_eq!(subscriber.check_timeouts(at(3000)).len(), 1);
+        assert_eq!(subscriber.on_packet(&packet(1, 0), at(3001)), vec![GooseEvent::StateChange{ key, stNum: 1, previous_stNum: Some(7), rollover: false }]);
+    }
+}
@@ -0,0 +1,257 @@
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::basic_decoder::{*};
+
+// (tag, name, required)
+const SV_PDU_FIELDS:[(u8,&str,bool);3]=[
+    (0x80,"noASDU",true),
+    (0x81,"security",false),
+    (0xa2,"asdu",true),
+];
+
+const SV_ASDU_FIELDS:[(u8,&str,bool);9]=[
+    (0x80,"svID",true),
+    (0x81,"datSet",false),
+    (0x82,"smpCnt",true),
+    (0x83,"confRev",true),
+    (0x84,"refrTm",false),
+    (0x85,"smpSynch",true),
+    (0x86,"smpRate",false),
+    (0x87,"seqData",true),
+    (0x88,"smpMod",false),
+];
+
+// looks the tag up in fields and marks it seen, shared by savPdu and ASDU decoding
+fn checkField(fields: &[(u8,&str,bool)], seen: &mut [bool], context: &str, tag: u8, pos: usize) ->Result<(),GooseError>{
+    let index=match fields.iter().position(|(field_tag,_,_)| *field_tag==tag){
+        Some(index) => index,
+        None => {
+            return Err(GooseError{ message: format!("unexpected {} tag 0x{:02x}",context,tag), pos});
+        }
+    };
+    if seen[index]{
+        return Err(GooseError{ message: format!("duplicate {} field {} (0x{:02x})",context,fields[index].1,tag), pos});
+    }
+    seen[index]=true;
+    Ok(())
+}
+
+fn checkRequired(fields: &[(u8,&str,bool)], seen: &[bool], context: &str, pos: usize) ->Result<(),GooseError>{
+    for (index,(tag,name,required)) in fields.iter().enumerate(){
+        if *required && !seen[index]{
+            return Err(GooseError{ message: format!("missing {} field {} (0x{:02x})",context,name,tag), pos});
+        }
+    }
+    Ok(())
+}
+
+pub fn decodeIECSVPdu(buffer: &[u8], pos: &mut usize) -> Result<IECSVPdu,GooseError>{
+    let mut tag:u8=0;
+    let mut length:usize=0;
+
+    let mut pdu = IECSVPdu::default();
+
+    *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
+    if tag != 0x60{
+        return Err(GooseError{ message: "first tag != 60".into(), pos: *pos});
+    }
+    let end=*pos+length;
+    // fields may not run past the end of the savPdu
+    let buffer=&buffer[..end];
+
+    let mut seen=[false;3];
+
+    while *pos < end {
+        let field_pos=*pos;
+        *pos=decode_tag_length(&mut tag,&mut length,buffer,*pos)?;
+        checkField(&SV_PDU_FIELDS, &mut seen, "savPdu", tag, field_pos)?;
+
+        *pos=match tag{
+            0x80=>decode_unsigned(&mut pdu.noASDU,buffer,*pos,length)?,
+            0x81=>{
+                check_bounds(buffer,*pos,length)?;
+                pdu.security=Some(buffer[*pos..*pos+length].to_vec());
+                *pos+length
+            },
+            _=>decodeIECSVAsduSequence(&mut pdu.asdu,buffer,*pos,*pos+length)?,
+        };
+    }
+
+    checkRequired(&SV_PDU_FIELDS, &seen, "savPdu", *pos)?;
+
+    if pdu.noASDU as usize != pdu.asdu.len(){
+        return Err(GooseError{ message: format!("ASDU count {} does not match noASDU {}", pdu.asdu.len(), pdu.noASDU), pos: *pos});
+    }
+
+    Ok(pdu)
+}
+
+fn decodeIECSVAsduSequence(asdus: &mut Vec<IECSVAsdu>, buffer: &[u8], pos:usize, end:usize) ->Result<usize,GooseError>{
+    let mut tag:u8=0;
+    let mut length:usize=0;
+    let mut new_pos=pos;
+    let buffer=&buffer[..end];
+
+    while new_pos<end {
+        new_pos=decode_tag_length(&mut tag,&mut length,buffer,new_pos)?;
+        if tag != 0x30{
+            return Err(GooseError{ message: format!("unexpected ASDU tag 0x{:02x}",tag), pos: new_pos});
+        }
+        let mut asdu=IECSVAsdu::default();
+        new_pos=decodeIECSVAsdu(&mut asdu,buffer,new_pos,new_pos+length)?;
+        asdus.push(asdu);
+    }
+
+    Ok(new_pos)
+}
+
+fn decodeIECSVAsdu(asdu: &mut IECSVAsdu, buffer: &[u8], pos:usize, end:usize) ->Result<usize,GooseError>{
+    let mut tag:u8=0;
+    let mut length:usize=0;
+    let mut new_pos=pos;
+    let buffer=&buffer[..end];
+
+    let mut seen=[false;9];
+
+    while new_pos<end {
+        let field_pos=new_pos;
+        new_pos=decode_tag_length(&mut tag,&mut length,buffer,new_pos)?;
+        checkField(&SV_ASDU_FIELDS, &mut seen, "ASDU", tag, field_pos)?;
+
+        new_pos=match tag{
+            0x80=>decode_string(&mut asdu.svID,buffer,new_pos,length)?,
+            0x81=>{
+                let mut datSet=String::new();
+                let next_pos=decode_string(&mut datSet,buffer,new_pos,length)?;
+                asdu.datSet=Some(datSet);
+                next_pos
+            },
+            0x82=>{
+                let mut bytes=[0_u8;2];
+                let next_pos=decode_octet_string(&mut bytes,buffer,new_pos,length)?;
+                asdu.smpCnt=u16::from_be_bytes(bytes);
+                next_pos
+            },
+            0x83=>{
+                let mut bytes=[0_u8;4];
+                let next_pos=decode_octet_string(&mut bytes,buffer,new_pos,length)?;
+                asdu.confRev=u32::from_be_bytes(bytes);
+                next_pos
+            },
+            0x84=>{
+                let mut refrTm=UtcTime::default();
+                let next_pos=decode_utc_time(&mut refrTm,buffer,new_pos,length)?;
+                asdu.refrTm=Some(refrTm);
+                next_pos
+            },
+            0x85=>{
+                let mut bytes=[0_u8;1];
+                let next_pos=decode_octet_string(&mut bytes,buffer,new_pos,length)?;
+                asdu.smpSynch=bytes[0];
+                next_pos
+            },
+            0x86=>{
+                let mut bytes=[0_u8;2];
+                let next_pos=decode_octet_string(&mut bytes,buffer,new_pos,length)?;
+                asdu.smpRate=Some(u16::from_be_bytes(bytes));
+                next_pos
+            },
+            0x87=>decodeSeqData(&mut asdu.seqData,buffer,new_pos,length)?,
+            _=>{
+                let mut bytes=[0_u8;2];
+                let next_pos=decode_octet_string(&mut bytes,buffer,new_pos,length)?;
+                asdu.smpMod=Some(u16::from_be_bytes(bytes));
+                next_pos
+            },
+        };
+    }
+
+    checkRequired(&SV_ASDU_FIELDS, &seen, "ASDU", new_pos)?;
+
+    Ok(new_pos)
+}
+
+fn decodeSeqData(samples: &mut Vec<IECSVSample>, buffer: &[u8], pos:usize, length:usize) ->Result<usize,GooseError>{
+    // 9-2LE: 8 INT32 values, each followed by its quality
+    let expected=IECSVSample::LE_SAMPLES*IECSVSample::getSize();
+    if length != expected{
+        return Err(GooseError{ message: format!("seqData length {} is not the 9-2LE length {}",length,expected), pos});
+    }
+    check_bounds(buffer,pos,length)?;
+
+    for sample in buffer[pos..pos+length].chunks_exact(IECSVSample::getSize()){
+        samples.push(IECSVSample{
+            value: i32::from_be_bytes(sample[0..4].try_into().unwrap()),
+            quality: u32::from_be_bytes(sample[4..8].try_into().unwrap()),
+        });
+    }
+    Ok(pos+length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::{decodeSVPacket,encodeSVPacket,sizeSVPacket};
+
+    // 9-2LE frame of a merging unit: VLAN priority 4, one ASDU, smpSynch global, 4 currents and
+    // 4 voltages with IN flagged derived
+    fn recorded() -> Vec<u8>{
+        let mut frame = vec![
+            0x01, 0x0c, 0xcd, 0x04, 0x00, 0x01, 0x00, 0x1b, 0x2c, 0x3d, 0x4e, 0x5f,
+            0x81, 0x00, 0x80, 0x00, 0x88, 0xba,
+            0x40, 0x00, 0x00, 0x68, 0x00, 0x00, 0x00, 0x00,
+            0x60, 0x5e, 0x80, 0x01, 0x01, 0xa2, 0x59, 0x30, 0x57,
+            0x80, 0x06, b'M', b'U', b'0', b'1', b'0', b'1',
+            0x82, 0x02, 0x12, 0x34,
+            0x83, 0x04, 0x00, 0x00, 0x00, 0x01,
+            0x85, 0x01, 0x02,
+            0x87, 0x40,
+        ];
+        let samples: [(i32, u32);8] = [(1000, 0), (-500, 0), (-500, 0), (0, 0x2000), (635000, 0), (-317500, 0), (-317500, 0), (0, 0x2000)];
+        for (value, quality) in samples{
+            frame.extend_from_slice(&value.to_be_bytes());
+            frame.extend_from_slice(&quality.to_be_bytes());
+        }
+        frame
+    }
+
+    #[test]
+    fn recorded_frame_round_trip(){
+        let frame = recorded();
+        let pkt = decodeSVPacket(&frame, 0).unwrap().unwrap();
+        assert_eq!(pkt.sv_hdr.APPID, [0x40, 0x00]);
+        let vlan = pkt.eth_hdr.VLAN.as_ref().unwrap();
+        assert_eq!((vlan.priority, vlan.id), (4, 0));
+        assert_eq!(pkt.pdu.noASDU, 1);
+        let asdu = &pkt.pdu.asdu[0];
+        assert_eq!((asdu.svID.as_str(), asdu.smpCnt, asdu.confRev, asdu.smpSynch), ("MU0101", 0x1234, 1, 2));
+        assert_eq!(asdu.seqData.len(), IECSVSample::LE_SAMPLES);
+        assert_eq!(asdu.seqData[1], IECSVSample{ value: -500, quality: 0 });
+        assert_eq!(asdu.seqData[7], IECSVSample{ value: 0, quality: 0x2000 });
+
+        let mut buffer = vec![0_u8;sizeSVPacket(&pkt)];
+        let size = encodeSVPacket(&pkt, &mut buffer, 0).unwrap();
+        assert_eq!(&buffer[..size], &frame[..]);
+    }
+
+    #[test]
+    fn seqData_is_8_samples(){
+        // the recorded frame one sample short, with the lengths around it adjusted
+        let mut short = recorded();
+        short.truncate(short.len() - IECSVSample::getSize());
+        for (pos, length) in [(21, 0x60), (27, 0x56), (32, 0x51), (34, 0x4f), (57, 0x38)]{
+            short[pos] = length;
+        }
+        let e = decodeSVPacket(&short, 0).unwrap().unwrap_err();
+        assert_eq!((e.message.as_str(), e.pos), ("seqData length 56 is not the 9-2LE length 64", 58));
+
+        let mut pkt = decodeSVPacket(&recorded(), 0).unwrap().unwrap();
+        pkt.pdu.asdu[0].seqData.pop();
+        let mut buffer = vec![0_u8;sizeSVPacket(&pkt)];
+        let e = encodeSVPacket(&pkt, &mut buffer, 0).unwrap_err();
+        assert_eq!(e.message, "MU0101: seqData holds 7 samples, 9-2LE needs 8");
+    }
+}
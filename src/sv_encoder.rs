@@ -0,0 +1,156 @@
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+use crate::error::GooseError;
+use crate::types::{*};
+use crate::basic_encoder::{*};
+
+pub fn encodeIECSVPdu(pdu: & IECSVPdu, buffer: &mut[u8], pos:usize) ->Result<usize,GooseError>{
+    // first pass cacluate the length
+    let (savPduLength,asduSeqLength)=sizeIECSVPdu(pdu);
+    // second pass fill the buffer
+    fillIECSVPdu(pdu, buffer, pos, savPduLength, asduSeqLength)
+}
+
+// total number of bytes encodeIECSVPdu writes, including the 0x60 tag and length
+pub fn sizeSVPduFrame(pdu: & IECSVPdu) ->usize{
+    let (savPduLength,_)=sizeIECSVPdu(pdu);
+    1+size_length(savPduLength)+savPduLength
+}
+
+// 9-2LE fixes the width of these fields instead of using compressed integers
+fn seqDataBytes(asdu: & IECSVAsdu) ->Vec<u8>{
+    let mut bytes=Vec::with_capacity(asdu.seqData.len()*IECSVSample::getSize());
+    for sample in asdu.seqData.iter(){
+        bytes.extend_from_slice(&sample.value.to_be_bytes());
+        bytes.extend_from_slice(&sample.quality.to_be_bytes());
+    }
+    bytes
+}
+
+pub fn sizeIECSVPdu(pdu: & IECSVPdu) ->(usize,usize){
+
+    let mut savPduLength=0;
+
+    let mut size;
+    let fill= false;
+
+    size=encode_unsigned(0x80, pdu.noASDU, &mut [], 0, fill);
+    savPduLength+=1+size_length(size)+size;
+
+    if let Some(security) = &pdu.security{
+        size=encode_octet_string(0x81, security, &mut [], 0, fill);
+        savPduLength+=1+size_length(size)+size;
+    }
+
+    let mut asduSeqLength=0;
+    for asdu in pdu.asdu.iter(){
+        size=sizeIECSVAsdu(asdu);
+        asduSeqLength+=1+size_length(size)+size;
+    }
+    savPduLength+=1+size_length(asduSeqLength)+asduSeqLength;
+
+    (savPduLength,asduSeqLength)
+}
+
+pub fn sizeIECSVAsdu(asdu: & IECSVAsdu) ->usize{
+
+    let mut asduLength=0;
+
+    let mut size;
+    let fill= false;
+
+    size=encode_string(0x80, &asdu.svID, &mut [], 0, fill);
+    asduLength+=1+size_length(size)+size;
+
+    if let Some(datSet) = &asdu.datSet{
+        size=encode_string(0x81, datSet, &mut [], 0, fill);
+        asduLength+=1+size_length(size)+size;
+    }
+
+    size=encode_octet_string(0x82, &asdu.smpCnt.to_be_bytes(), &mut [], 0, fill);
+    asduLength+=1+size_length(size)+size;
+
+    size=encode_octet_string(0x83, &asdu.confRev.to_be_bytes(), &mut [], 0, fill);
+    asduLength+=1+size_length(size)+size;
+
+    if let Some(refrTm) = &asdu.refrTm{
+        size=encode_utc_time(0x84, refrTm, &mut [], 0, fill);
+        asduLength+=1+size_length(size)+size;
+    }
+
+    size=encode_octet_string(0x85, &[asdu.smpSynch], &mut [], 0, fill);
+    asduLength+=1+size_length(size)+size;
+
+    if let Some(smpRate) = asdu.smpRate{
+        size=encode_octet_string(0x86, &smpRate.to_be_bytes(), &mut [], 0, fill);
+        asduLength+=1+size_length(size)+size;
+    }
+
+    size=asdu.seqData.len()*IECSVSample::getSize();
+    asduLength+=1+size_length(size)+size;
+
+    if let Some(smpMod) = asdu.smpMod{
+        size=encode_octet_string(0x88, &smpMod.to_be_bytes(), &mut [], 0, fill);
+        asduLength+=1+size_length(size)+size;
+    }
+
+    asduLength
+}
+
+pub fn fillIECSVPdu(pdu: & IECSVPdu, buffer: &mut[u8], pos:usize, savPduLength: usize, asduSeqLength: usize) ->Result<usize,GooseError>{
+
+    let required=1+size_length(savPduLength)+savPduLength;
+    if pos+required > buffer.len(){
+        return Err(GooseError{ message: format!("buffer too short, savPdu needs {} bytes", required), pos});
+    }
+
+    if let Some(asdu) = pdu.asdu.iter().find(|asdu| asdu.seqData.len() != IECSVSample::LE_SAMPLES){
+        return Err(GooseError{ message: format!("{}: seqData holds {} samples, 9-2LE needs {}", asdu.svID, asdu.seqData.len(), IECSVSample::LE_SAMPLES), pos});
+    }
+
+    let mut new_pos=pos;
+    let fill= true;
+
+    new_pos=encode_tag_length(0x60,savPduLength,buffer,new_pos,fill );
+
+    new_pos=encode_unsigned(0x80, pdu.noASDU, buffer, new_pos, fill);
+
+    if let Some(security) = &pdu.security{
+        new_pos=encode_octet_string(0x81, security, buffer, new_pos, fill);
+    }
+
+    new_pos=encode_tag_length(0xa2,asduSeqLength,buffer,new_pos,fill );
+
+    for asdu in pdu.asdu.iter(){
+        new_pos=encode_tag_length(0x30,sizeIECSVAsdu(asdu),buffer,new_pos,fill );
+
+        new_pos=encode_string(0x80, &asdu.svID, buffer, new_pos, fill);
+
+        if let Some(datSet) = &asdu.datSet{
+            new_pos=encode_string(0x81, datSet, buffer, new_pos, fill);
+        }
+
+        new_pos=encode_octet_string(0x82, &asdu.smpCnt.to_be_bytes(), buffer, new_pos, fill);
+
+        new_pos=encode_octet_string(0x83, &asdu.confRev.to_be_bytes(), buffer, new_pos, fill);
+
+        if let Some(refrTm) = &asdu.refrTm{
+            new_pos=encode_utc_time(0x84, refrTm, buffer, new_pos, fill);
+        }
+
+        new_pos=encode_octet_string(0x85, &[asdu.smpSynch], buffer, new_pos, fill);
+
+        if let Some(smpRate) = asdu.smpRate{
+            new_pos=encode_octet_string(0x86, &smpRate.to_be_bytes(), buffer, new_pos, fill);
+        }
+
+        new_pos=encode_octet_string(0x87, &seqDataBytes(asdu), buffer, new_pos, fill);
+
+        if let Some(smpMod) = asdu.smpMod{
+            new_pos=encode_octet_string(0x88, &smpMod.to_be_bytes(), buffer, new_pos, fill);
+        }
+    }
+
+    Ok(new_pos)
+}
@@ -1,6 +1,7 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 use serde::{Serialize, Deserialize};
+pub use crate::utc_time::{UtcTime,TimeQuality};
 
 #[derive(Debug,Serialize, Deserialize, Clone)]
 pub enum IECData{
@@ -16,6 +17,7 @@ pub enum IECData{
     int8u(u8),
     int16u(u16),
     int32u(u32),
+    int64u(u64),
 
     float32(f32),
     float64(f64),
@@ -24,19 +26,69 @@ pub enum IECData{
     mms_string(String),
     bit_string{ padding: u8, val: Vec<u8> },
     octet_string(Vec<u8>),
-    utc_time([u8;8])
+    utc_time(UtcTime)
 }
+// IEEE 802.1Q tag control information
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq)]
+pub struct VLANTag {
+    // priority code point, 0..=7
+    pub priority: u8,
+    // drop eligible indicator
+    pub dei: bool,
+    // 12 bit VLAN ID, 0 only carries the priority
+    pub id: u16,
+}
+
+impl Default for VLANTag {
+    // IEC 61850-8-1 default priority for GOOSE
+    fn default() -> VLANTag{
+        VLANTag{ priority: 4, dei: false, id: 0 }
+    }
+}
+
+impl VLANTag {
+    pub fn new(id: u16) -> VLANTag{
+        VLANTag{ id, ..Default::default() }
+    }
+
+    pub fn from_tci(tci: u16) -> VLANTag{
+        VLANTag{ priority: (tci >> 13) as u8, dei: tci & 0x1000 != 0, id: tci & 0x0fff }
+    }
+
+    pub fn to_tci(&self) -> u16{
+        let dei = if self.dei {0x1000} else {0};
+        ((self.priority as u16 & 0x07) << 13) | dei | (self.id & 0x0fff)
+    }
+}
+
 #[derive(Serialize,Deserialize,Debug,Default,Clone)]
 pub struct EthernetHeader {
     pub srcAddr:[u8;6],
     pub dstAddr:[u8;6],
-    pub VLANID:Option<u16>,
+    // 802.1Q customer tag, EtherType 0x8100
+    pub VLAN:Option<VLANTag>,
+    // 802.1ad service tag in front of VLAN, EtherType 0x88A8
+    pub outerVLAN:Option<VLANTag>,
+    // HSR tag behind the VLAN tags, EtherType 0x892F
+    pub HSR:Option<IECHSR>,
+}
+
+impl EthernetHeader {
+    // bytes the VLAN tags add to the frame
+    pub fn tagsSize(&self) -> usize{
+        4 * (self.VLAN.is_some() as usize + self.outerVLAN.is_some() as usize)
+    }
 }
 
 #[derive(Serialize,Deserialize,Debug,Default,Clone)]
 pub struct IECGooseHeader {
     pub APPID:[u8;2],
-    pub length: u16
+    pub length: u16,
+    // reserved 1 bit 15, edition 2 simulation/test flag, has to agree with IECGoosePdu::simulation
+    pub simulation: bool,
+    // reserved 1 lower bits, IEC 62351-6 extension length, set by the encoder like length
+    pub security_length: u16,
+    pub reserved2: u16,
 }
 
 impl IECGooseHeader {
@@ -51,7 +103,7 @@ pub struct IECGoosePdu {
     pub timeAllowedtoLive: u32,
     pub datSet: String,
     pub goID: String,
-    pub t: [u8;8],
+    pub t: UtcTime,
     pub stNum: u32,
     pub sqNum: u32,
     pub simulation: bool,
@@ -67,7 +119,7 @@ impl IECGoosePdu {
     }
 }
 
-#[derive(Serialize,Deserialize,Debug,Clone)]
+#[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,Eq,Hash)]
 pub enum IECPRPLAN{
     LAN_A,
     LAN_B,
@@ -80,6 +132,27 @@ pub struct IECPRP1 { //IEC 62439-3 Parallel Redundancy Protocol (PRP)
     pub frame_size: u16,
 }
 
+impl IECPRP1 {
+    pub fn getSize() -> usize{
+        6
+    }
+}
+
+#[derive(Serialize,Deserialize,Debug,Default,Clone)]
+pub struct IECHSR { //IEC 62439-3 High-availability Seamless Redundancy (HSR)
+    // 4 bit path identifier, the lowest bit tells the ring port
+    pub path: u8,
+    // LSDU size from the path field up to the end of the payload, set by the encoder
+    pub frame_size: u16,
+    pub sequence: u16,
+}
+
+impl IECHSR {
+    pub fn getSize() -> usize{
+        6
+    }
+}
+
 #[derive(Serialize,Deserialize,Debug,Default,Clone)]
 pub struct IECGoosePacket{
     pub eth_hdr: EthernetHeader,
@@ -87,3 +160,50 @@ pub struct IECGoosePacket{
     pub pdu: IECGoosePdu,
     pub prp: Option<IECPRP1>,
 }
+
+// IEC 61850-9-2 Sampled Values, the APPID header is the same as for GOOSE
+pub type IECSVHeader = IECGooseHeader;
+
+// one seqData entry, 9-2LE carries 8 of them: 4 currents then 4 voltages
+#[derive(Serialize,Deserialize,Debug,Default,Clone,Copy,PartialEq)]
+pub struct IECSVSample {
+    pub value: i32,
+    pub quality: u32,
+}
+
+impl IECSVSample {
+    // entries in the seqData of a 9-2LE ASDU
+    pub const LE_SAMPLES:usize = 8;
+
+    pub fn getSize() -> usize{
+        8
+    }
+}
+
+#[derive(Serialize,Deserialize,Debug,Default,Clone)]
+pub struct IECSVAsdu {
+    pub svID: String,
+    pub datSet: Option<String>,
+    pub smpCnt: u16,
+    pub confRev: u32,
+    pub refrTm: Option<UtcTime>,
+    pub smpSynch: u8,
+    pub smpRate: Option<u16>,
+    pub seqData: Vec<IECSVSample>,
+    pub smpMod: Option<u16>,
+}
+
+#[derive(Serialize,Deserialize,Debug,Default,Clone)]
+pub struct IECSVPdu {
+    pub noASDU: u32,
+    pub security: Option<Vec<u8>>,
+    pub asdu: Vec<IECSVAsdu>,
+}
+
+#[derive(Serialize,Deserialize,Debug,Default,Clone)]
+pub struct IECSVPacket{
+    pub eth_hdr: EthernetHeader,
+    pub sv_hdr: IECSVHeader,
+    pub pdu: IECSVPdu,
+    pub prp: Option<IECPRP1>,
+}
@@ -0,0 +1,352 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use crate::error::GooseError;
+
+const FRACTION_SCALE:u64 = 1 << 24;
+const NANOS_PER_SEC:u64 = 1_000_000_000;
+
+// TimeQuality octet of an IEC 61850 UtcTime (8-1 8.1.3.7)
+#[derive(Serialize,Deserialize,Debug,Default,Clone,Copy,PartialEq,Eq)]
+pub struct TimeQuality {
+    pub leap_seconds_known: bool,
+    pub clock_failure: bool,
+    pub clock_not_synchronized: bool,
+    // number of significant bits in the fraction, 31 means unspecified
+    pub time_accuracy: u8,
+}
+
+impl TimeQuality {
+    pub fn from_byte(byte: u8) -> TimeQuality{
+        TimeQuality{
+            leap_seconds_known: byte & 0x80 != 0,
+            clock_failure: byte & 0x40 != 0,
+            clock_not_synchronized: byte & 0x20 != 0,
+            time_accuracy: byte & 0x1f,
+        }
+    }
+
+    pub fn to_byte(&self) -> u8{
+        let mut byte = self.time_accuracy & 0x1f;
+        if self.leap_seconds_known{
+            byte |= 0x80;
+        }
+        if self.clock_failure{
+            byte |= 0x40;
+        }
+        if self.clock_not_synchronized{
+            byte |= 0x20;
+        }
+        byte
+    }
+}
+
+// IEC 61850 UtcTime: seconds since 1970-01-01 00:00:00 UTC, a 24bit binary fraction of a second and the time quality
+#[derive(Serialize,Deserialize,Debug,Default,Clone,Copy,PartialEq,Eq)]
+#[serde(into = "UtcTimeRepr", try_from = "UtcTimeRepr")]
+pub struct UtcTime {
+    pub seconds: u32,
+    pub fraction: u32,
+    pub quality: TimeQuality,
+}
+
+impl UtcTime {
+    pub fn now() -> UtcTime{
+        UtcTime::from_system_time(SystemTime::now(), TimeQuality::default()).expect("system time between 1970 and 2106")
+    }
+
+    pub fn from_bytes(bytes: &[u8;8]) -> UtcTime{
+        UtcTime{
+            seconds: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            fraction: u32::from_be_bytes([0, bytes[4], bytes[5], bytes[6]]),
+            quality: TimeQuality::from_byte(bytes[7]),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8;8]{
+        let mut bytes = [0_u8;8];
+        bytes[0..4].copy_from_slice(&self.seconds.to_be_bytes());
+        bytes[4..7].copy_from_slice(&(self.fraction & 0x00ff_ffff).to_be_bytes()[1..]);
+        bytes[7] = self.quality.to_byte();
+        bytes
+    }
+
+    // the fraction has a resolution of about 60ns, nanoseconds are rounded to the nearest step
+    pub fn from_nanos(nanos: u64, quality: TimeQuality) -> Result<UtcTime,GooseError>{
+        let mut seconds = nanos / NANOS_PER_SEC;
+        let mut fraction = ((nanos % NANOS_PER_SEC) * FRACTION_SCALE + NANOS_PER_SEC / 2) / NANOS_PER_SEC;
+        if fraction == FRACTION_SCALE{
+            seconds += 1;
+            fraction = 0;
+        }
+        if seconds > u32::MAX as u64{
+            return Err(GooseError{ message: format!("{} seconds does not fit UtcTime", seconds), pos: 0});
+        }
+        Ok(UtcTime{ seconds: seconds as u32, fraction: fraction as u32, quality })
+    }
+
+    // every fraction maps to a distinct nanosecond value, so from_nanos(to_nanos()) is lossless
+    pub fn to_nanos(&self) -> u64{
+        self.seconds as u64 * NANOS_PER_SEC + self.subsec_nanos() as u64
+    }
+
+    pub fn subsec_nanos(&self) -> u32{
+        (((self.fraction as u64 & 0x00ff_ffff) * NANOS_PER_SEC + FRACTION_SCALE / 2) / FRACTION_SCALE) as u32
+    }
+
+    pub fn from_system_time(time: SystemTime, quality: TimeQuality) -> Result<UtcTime,GooseError>{
+        let since_the_epoch = match time.duration_since(UNIX_EPOCH){
+            Ok(duration) => duration,
+            Err(_) => {
+                return Err(GooseError{ message: "time before 1970 does not fit UtcTime".into(), pos: 0});
+            }
+        };
+        let nanos = u64::try_from(since_the_epoch.as_nanos()).unwrap_or(u64::MAX);
+        UtcTime::from_nanos(nanos, quality)
+    }
+
+    pub fn to_system_time(&self) -> SystemTime{
+        UNIX_EPOCH + Duration::new(self.seconds as u64, self.subsec_nanos())
+    }
+}
+
+impl From<UtcTime> for SystemTime {
+    fn from(time: UtcTime) -> SystemTime{
+        time.to_system_time()
+    }
+}
+
+// days since 1970-01-01 to (year, month, day), Howard Hinnant's civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32){
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64{
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * if month > 2 { month - 3 } else { month + 9 } + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn days_in_month(year: i64, month: u32) -> u32{
+    match month{
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// ISO-8601 with nanosecond digits, e.g. 2024-05-01T12:34:56.123456716Z
+impl fmt::Display for UtcTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let seconds = self.seconds as i64;
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+        let time_of_day = seconds.rem_euclid(86400);
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+            year, month, day, time_of_day / 3600, time_of_day / 60 % 60, time_of_day % 60, self.subsec_nanos())
+    }
+}
+
+fn parse_digits(text: &str, pos: usize, count: usize) -> Result<u32,GooseError>{
+    match text.get(pos..pos+count){
+        Some(digits) if digits.bytes().all(|b| b.is_ascii_digit()) => Ok(digits.parse().unwrap_or(0)),
+        _ => Err(GooseError{ message: format!("expected {} digits in timestamp '{}'", count, text), pos}),
+    }
+}
+
+fn expect_char(text: &str, pos: usize, expected: u8) -> Result<(),GooseError>{
+    if text.as_bytes().get(pos) == Some(&expected){
+        Ok(())
+    }
+    else{
+        Err(GooseError{ message: format!("expected '{}' in timestamp '{}'", expected as char, text), pos})
+    }
+}
+
+// parses the Display form, YYYY-MM-DDTHH:MM:SS[.fraction]Z, with an unset quality
+impl FromStr for UtcTime {
+    type Err = GooseError;
+
+    fn from_str(text: &str) -> Result<UtcTime,GooseError> {
+        let year = parse_digits(text, 0, 4)? as i64;
+        expect_char(text, 4, b'-')?;
+        let month = parse_digits(text, 5, 2)?;
+        expect_char(text, 7, b'-')?;
+        let day = parse_digits(text, 8, 2)?;
+        expect_char(text, 10, b'T')?;
+        let hour = parse_digits(text, 11, 2)?;
+        expect_char(text, 13, b':')?;
+        let minute = parse_digits(text, 14, 2)?;
+        expect_char(text, 16, b':')?;
+        let second = parse_digits(text, 17, 2)?;
+
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 59{
+            return Err(GooseError{ message: format!("timestamp '{}' out of range", text), pos: 0});
+        }
+
+        let mut pos = 19;
+        let mut nanos:u64 = 0;
+        if text.as_bytes().get(pos) == Some(&b'.'){
+            pos += 1;
+            let digits = text[pos..].bytes().take_while(|b| b.is_ascii_digit()).count();
+            if digits == 0 || digits > 9{
+                return Err(GooseError{ message: format!("expected 1 to 9 fraction digits in timestamp '{}'", text), pos});
+            }
+            nanos = parse_digits(text, pos, digits)? as u64 * 10_u64.pow(9 - digits as u32);
+            pos += digits;
+        }
+        expect_char(text, pos, b'Z')?;
+        if pos + 1 != text.len(){
+            return Err(GooseError{ message: format!("trailing characters in timestamp '{}'", text), pos: pos + 1});
+        }
+
+        let seconds = days_from_civil(year, month, day) * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+        if seconds < 0 || seconds > u32::MAX as i64{
+            return Err(GooseError{ message: format!("timestamp '{}' does not fit UtcTime", text), pos: 0});
+        }
+        UtcTime::from_nanos(seconds as u64 * NANOS_PER_SEC + nanos, TimeQuality::default())
+    }
+}
+
+// serde form, a readable timestamp next to the quality flags
+#[derive(Serialize,Deserialize)]
+struct UtcTimeRepr {
+    time: String,
+    quality: TimeQuality,
+}
+
+impl From<UtcTime> for UtcTimeRepr {
+    fn from(time: UtcTime) -> UtcTimeRepr{
+        UtcTimeRepr{ time: time.to_string(), quality: time.quality }
+    }
+}
+
+impl TryFrom<UtcTimeRepr> for UtcTime {
+    type Error = GooseError;
+
+    fn try_from(repr: UtcTimeRepr) -> Result<UtcTime,GooseError>{
+        let mut time: UtcTime = repr.time.parse()?;
+        time.quality = repr.quality;
+        Ok(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(seconds: u32, fraction: u32) -> UtcTime{
+        UtcTime{ seconds, fraction, quality: TimeQuality::default() }
+    }
+
+    #[test]
+    fn iso_8601(){
+        assert_eq!(time(0, 0).to_string(), "1970-01-01T00:00:00.000000000Z");
+        assert_eq!(time(1714566896, 0x80_0000).to_string(), "2024-05-01T12:34:56.500000000Z");
+        assert_eq!(time(u32::MAX, 0xff_ffff).to_string(), "2106-02-07T06:28:15.999999940Z");
+        assert_eq!("2024-05-01T12:34:56.5Z".parse::<UtcTime>().unwrap(), time(1714566896, 0x80_0000));
+        assert_eq!("2000-02-29T00:00:00Z".parse::<UtcTime>().unwrap(), time(951782400, 0));
+
+        for seconds in [0, 68169600, 951868800, 1714566896, u32::MAX]{
+            for fraction in (0..1 << 24).step_by(4099).chain([0xff_ffff]){
+                let utc = time(seconds, fraction);
+                assert_eq!(utc.to_string().parse::<UtcTime>().unwrap(), utc);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_timestamps(){
+        let errors = [
+            ("2024-13-01T00:00:00Z", 0),
+            ("1900-02-29T00:00:00Z", 0),
+            ("2024-05-01T24:00:00Z", 0),
+            ("2024-05-01 12:34:56Z", 10),
+            ("2024-05-01T12:34:56.Z", 20),
+            ("2024-05-01T12:34:56.1234567890Z", 20),
+            ("2024-05-01T12:34:56", 19),
+            ("2024-05-01T12:34:56Zx", 20),
+            ("24-05-01T12:34:56Z", 0),
+            // before the epoch and past 2106
+            ("1969-12-31T23:59:59Z", 0),
+            ("2106-02-07T06:28:16Z", 0),
+        ];
+        for (text, pos) in errors{
+            let e = text.parse::<UtcTime>().unwrap_err();
+            assert_eq!(e.pos, pos, "{}: {}", text, e.message);
+        }
+        // rounds up past the last representable second
+        assert!("2106-02-07T06:28:15.999999999Z".parse::<UtcTime>().is_err());
+    }
+
+    #[test]
+    fn fraction_and_nanoseconds(){
+        // one fraction step is 59.6ns
+        assert_eq!(time(0, 1).subsec_nanos(), 60);
+        assert_eq!(time(0, 0x80_0000).subsec_nanos(), 500_000_000);
+        assert_eq!(UtcTime::from_nanos(29, TimeQuality::default()).unwrap().fraction, 0);
+        assert_eq!(UtcTime::from_nanos(30, TimeQuality::default()).unwrap().fraction, 1);
+        // the last 30ns of a second round up into the next one
+        assert_eq!(UtcTime::from_nanos(999_999_999, TimeQuality::default()).unwrap(), time(1, 0));
+        assert_eq!(UtcTime::from_nanos(999_999_940, TimeQuality::default()).unwrap(), time(0, 0xff_ffff));
+
+        for fraction in (0..1 << 24).step_by(7){
+            let utc = time(1714566896, fraction);
+            assert_eq!(UtcTime::from_nanos(utc.to_nanos(), TimeQuality::default()).unwrap(), utc);
+        }
+        assert!(UtcTime::from_nanos((u32::MAX as u64 + 1) * NANOS_PER_SEC, TimeQuality::default()).is_err());
+    }
+
+    #[test]
+    fn system_time(){
+        let quality = TimeQuality{ time_accuracy: 10, ..Default::default() };
+        let utc = UtcTime::from_system_time(UNIX_EPOCH + Duration::new(1714566896, 250_000_000), quality).unwrap();
+        assert_eq!(utc, UtcTime{ seconds: 1714566896, fraction: 0x40_0000, quality });
+        assert_eq!(SystemTime::from(utc), UNIX_EPOCH + Duration::new(1714566896, 250_000_000));
+
+        let e = UtcTime::from_system_time(UNIX_EPOCH - Duration::from_secs(1), quality).unwrap_err();
+        assert_eq!(e.message, "time before 1970 does not fit UtcTime");
+        assert!(UtcTime::from_system_time(UNIX_EPOCH + Duration::from_secs(1 << 32), quality).is_err());
+    }
+
+    #[test]
+    fn time_quality_bits(){
+        let quality = TimeQuality{ leap_seconds_known: true, clock_failure: false, clock_not_synchronized: true, time_accuracy: 10 };
+        assert_eq!(quality.to_byte(), 0xaa);
+        assert_eq!(TimeQuality::from_byte(0x5f), TimeQuality{ leap_seconds_known: false, clock_failure: true, clock_not_synchronized: false, time_accuracy: 31 });
+        for byte in 0..=255{
+            assert_eq!(TimeQuality::from_byte(byte).to_byte(), byte);
+        }
+
+        let utc = UtcTime{ seconds: 0x6632_3a70, fraction: 0x12_3456, quality };
+        assert_eq!(utc.to_bytes(), [0x66, 0x32, 0x3a, 0x70, 0x12, 0x34, 0x56, 0xaa]);
+        assert_eq!(UtcTime::from_bytes(&utc.to_bytes()), utc);
+    }
+
+    #[test]
+    fn serde_repr(){
+        let utc = UtcTime{ seconds: 1714566896, fraction: 0x80_0000, quality: TimeQuality{ time_accuracy: 10, ..Default::default() } };
+        let json = serde_json::to_string(&utc).unwrap();
+        assert_eq!(json, r#"{"time":"2024-05-01T12:34:56.500000000Z","quality":{"leap_seconds_known":false,"clock_failure":false,"clock_not_synchronized":false,"time_accuracy":10}}"#);
+        assert_eq!(serde_json::from_str::<UtcTime>(&json).unwrap(), utc);
+
+        let invalid = json.replace("12:34:56", "12:34:66");
+        assert!(serde_json::from_str::<UtcTime>(&invalid).is_err());
+    }
+}